@@ -0,0 +1,57 @@
+//! Allocations per message when sending byte buffers, with and without recycling.
+//! Run with `cargo bench --bench byte_pool`
+use buffy::bytes::byte_channel;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+const MESSAGES: usize = 100_000;
+const MESSAGE_LEN: usize = 4096;
+
+/// Counts every allocation made by the process
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+                System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+        }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn measure(name: &str, recycle: bool) {
+        let (tx, rx) = byte_channel(64);
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let producer = thread::spawn(move || {
+                for i in 0..MESSAGES {
+                        let mut buf = if recycle { tx.acquire_buf() } else { Vec::new() };
+                        buf.resize(MESSAGE_LEN, i as u8);
+                        tx.send(buf).unwrap();
+                }
+        });
+
+        while let Ok(buf) = rx.recv() {
+                if recycle {
+                        rx.recycle(buf);
+                }
+        }
+        producer.join().unwrap();
+
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        println!("{:<10} {:>8} allocations  {:>10?}", name, allocations, start.elapsed());
+}
+
+fn main() {
+        measure("fresh", false);
+        measure("recycled", true);
+}
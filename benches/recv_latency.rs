@@ -0,0 +1,42 @@
+//! Receive latency under a bursty producer, with and without spinning.
+//! Run with `cargo bench --bench recv_latency`
+use buffy::sender::Sender;
+use buffy::receiver::Receiver;
+use buffy::shared_state::{unbounded, channel_with_spin};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BURSTS: usize = 200;
+const BURST_LEN: usize = 50;
+
+fn measure(name: &str, (tx, rx): (Sender<Instant>, Receiver<Instant>)) {
+        let producer = thread::spawn(move || {
+                for _ in 0..BURSTS {
+                        for _ in 0..BURST_LEN {
+                                tx.send(Instant::now()).unwrap();
+                                // a short gap inside the burst, long enough for the queue to empty
+                                let gap = Instant::now();
+                                while gap.elapsed() < Duration::from_micros(2) {
+                                        std::hint::spin_loop();
+                                }
+                        }
+                        thread::sleep(Duration::from_micros(200));
+                }
+        });
+
+        let mut latencies = Vec::with_capacity(BURSTS * BURST_LEN);
+        while let Ok(sent) = rx.recv() {
+                latencies.push(sent.elapsed());
+        }
+        producer.join().unwrap();
+
+        latencies.sort();
+        let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+        let p99 = latencies[latencies.len() * 99 / 100];
+        println!("{:<12} mean {:>10?}  p99 {:>10?}", name, mean, p99);
+}
+
+fn main() {
+        measure("park", unbounded());
+        measure("spin 1000", channel_with_spin(1000));
+}
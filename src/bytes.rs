@@ -0,0 +1,133 @@
+use crate::shared_state::{bounded, ChannelError, SendError};
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::io;
+
+/// Most spare buffers kept for reuse on a channel with no capacity limit
+const MAX_POOLED_BUFS: usize = 64;
+
+/// Create a bounded channel of byte buffers that hands spent buffers back to
+/// the senders. Receivers give a buffer back with `recycle` once they are done
+/// with it, and senders pick one up with `acquire_buf` instead of allocating
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero
+pub fn byte_channel(capacity: usize) -> (Sender<Vec<u8>>, Receiver<Vec<u8>>) {
+        bounded(capacity)
+}
+
+impl Sender<Vec<u8>> {
+        /// An empty buffer to fill and send, reusing a recycled one if there is one
+        pub fn acquire_buf(&self) -> Vec<u8> {
+                self.state.recycled.lock().pop().unwrap_or_default()
+        }
+}
+
+/// Every `write` sends the bytes as one message, in a recycled buffer when there
+/// is one. `write!` can split its output over several writes, so wrap the sender
+/// in an `io::LineWriter` to get one message per line. A closed channel, or one
+/// without receivers, fails with `BrokenPipe`
+impl io::Write for Sender<Vec<u8>> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if buf.is_empty() {
+                        return Ok(0);
+                }
+                let mut message = self.acquire_buf();
+                message.extend_from_slice(buf);
+                match self.send(message) {
+                        Ok(()) => Ok(buf.len()),
+                        Err(SendError { error: ChannelError::NoReceivers, .. }) => {
+                                Err(io::Error::new(io::ErrorKind::BrokenPipe, "no receivers left"))
+                        }
+                        Err(SendError { error, .. }) => {
+                                Err(io::Error::new(io::ErrorKind::BrokenPipe, format!("{:?}", error)))
+                        }
+                }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+        }
+}
+
+impl Receiver<Vec<u8>> {
+        /// Give a buffer back for senders to reuse. It is cleared but keeps its
+        /// allocation; once the pool holds as many buffers as the channel does, extra
+        /// ones are just freed
+        pub fn recycle(&self, mut buf: Vec<u8>) {
+                buf.clear();
+                let mut recycled = self.state.recycled.lock();
+                if recycled.len() < self.state.capacity.unwrap_or(MAX_POOLED_BUFS) {
+                        recycled.push(buf);
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use std::io::Write;
+        use std::thread;
+
+        #[test]
+        fn test_buffers_are_reused() {
+                let (tx, rx) = byte_channel(2);
+                let mut buf = tx.acquire_buf();
+                buf.extend_from_slice(&[1; 512]);
+                let ptr = buf.as_ptr();
+                tx.send(buf).unwrap();
+
+                let buf = rx.recv().unwrap();
+                assert_eq!(buf.len(), 512);
+                rx.recycle(buf);
+
+                let buf = tx.acquire_buf();
+                assert!(buf.is_empty());
+                assert!(buf.capacity() >= 512);
+                assert_eq!(buf.as_ptr(), ptr);
+        }
+
+        #[test]
+        fn test_pool_is_capped() {
+                let (tx, rx) = byte_channel(2);
+                for _ in 0..5 {
+                        rx.recycle(Vec::with_capacity(8));
+                }
+                assert_eq!(tx.state.recycled.lock().len(), 2);
+        }
+
+        #[test]
+        fn test_pipeline() {
+                let (tx, rx) = byte_channel(4);
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..100u8 {
+                                let mut buf = tx.acquire_buf();
+                                buf.extend_from_slice(&[i; 64]);
+                                tx.send(buf).unwrap();
+                        }
+                });
+                for i in 0..100u8 {
+                        let buf = rx.recv().unwrap();
+                        assert!(buf.iter().all(|&byte| byte == i));
+                        rx.recycle(buf);
+                }
+                tx_thread.join().unwrap();
+        }
+
+        #[test]
+        fn test_write_formatted_lines() {
+                let (tx, rx) = byte_channel(8);
+                let mut writer = io::LineWriter::new(tx);
+                for i in 0..3 {
+                        writeln!(writer, "line {} of {}", i, 3).unwrap();
+                }
+                for i in 0..3 {
+                        assert_eq!(rx.recv().unwrap(), format!("line {} of 3\n", i).into_bytes());
+                }
+
+                drop(rx);
+                let err = writeln!(writer, "nobody is listening").unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        }
+}
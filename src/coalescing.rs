@@ -0,0 +1,100 @@
+use crate::shared_state::channel_with_queue;
+use crate::queue::Queue;
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Queue that keeps only the latest value for each key, in first-sent key order
+pub struct Coalesced<K, V> {
+        pub order: VecDeque<K>,
+        pub values: HashMap<K, V>,
+}
+
+impl<K, V> Default for Coalesced<K, V> {
+        fn default() -> Self {
+                Coalesced { order: VecDeque::new(), values: HashMap::new() }
+        }
+}
+
+impl<K: Eq + Hash + Clone, V> Coalesced<K, V> {
+        /// Queue `value` for `key`, replacing any value still waiting for that key
+        pub fn insert(&mut self, key: K, value: V) {
+                if self.values.insert(key.clone(), value).is_none() {
+                        self.order.push_back(key);
+                }
+        }
+}
+
+impl<K: Eq + Hash + Clone, V> Queue<(K, V)> for Coalesced<K, V> {
+        fn push_back(&mut self, (key, value): (K, V)) {
+                self.insert(key, value);
+        }
+
+        /// Pop the oldest key along with its latest value
+        fn pop_front(&mut self) -> Option<(K, V)> {
+                let key = self.order.pop_front()?;
+                let value = self.values.remove(&key).unwrap();
+                Some((key, value))
+        }
+
+        fn len(&self) -> usize {
+                self.order.len()
+        }
+
+        fn is_empty(&self) -> bool {
+                self.order.is_empty()
+        }
+}
+
+/// Sender part of a coalescing channel
+pub type CoalescingSender<K, V> = Sender<(K, V), Coalesced<K, V>>;
+
+/// Receiver part of a coalescing channel
+pub type CoalescingReceiver<K, V> = Receiver<(K, V), Coalesced<K, V>>;
+
+/// Create a channel of `(key, value)` pairs where sending to a key that is still
+/// queued replaces its value, keeping the key's place in line
+pub fn coalescing_channel<K: Eq + Hash + Clone, V>() -> (CoalescingSender<K, V>, CoalescingReceiver<K, V>) {
+        channel_with_queue(Coalesced::default())
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::shared_state::{ChannelError, SendError};
+
+        #[test]
+        fn test_latest_value_wins() {
+                let (tx, rx) = coalescing_channel();
+                tx.send(("a", 1)).unwrap();
+                tx.send(("b", 1)).unwrap();
+                tx.send(("a", 2)).unwrap();
+                tx.send(("c", 1)).unwrap();
+                tx.send(("b", 3)).unwrap();
+                assert_eq!(rx.recv().unwrap(), ("a", 2));
+                assert_eq!(rx.recv().unwrap(), ("b", 3));
+                assert_eq!(rx.recv().unwrap(), ("c", 1));
+                assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelEmpty)));
+        }
+
+        #[test]
+        fn test_key_requeued_after_recv() {
+                let (tx, rx) = coalescing_channel();
+                tx.send((1, "old")).unwrap();
+                assert_eq!(rx.recv().unwrap(), (1, "old"));
+                tx.send((1, "new")).unwrap();
+                drop(tx);
+                assert_eq!(rx.recv().unwrap(), (1, "new"));
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_send_without_receivers_hands_back_pair() {
+                let (tx, rx) = coalescing_channel();
+                drop(rx);
+                let err = tx.send(("a", 1)).unwrap_err();
+                assert!(matches!(err, SendError { item: ("a", 1), error: ChannelError::NoReceivers }));
+                assert_eq!(format!("{:?}", tx), "Sender { queued: 0, closed: false, senders: 1 }");
+        }
+}
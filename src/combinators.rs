@@ -0,0 +1,242 @@
+use crate::shared_state::{unbounded, ChannelError, CloseReason};
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Funnel several receivers into one. Each input gets a forwarding thread, and
+/// the returned receiver is closed once every input has closed
+pub fn merge<T: Send + 'static>(receivers: Vec<Receiver<T>>) -> Receiver<T> {
+        let (sender, receiver) = unbounded();
+        let remaining = Arc::new(AtomicUsize::new(receivers.len()));
+        if receivers.is_empty() {
+                sender.close_with(CloseReason::Normal);
+        }
+
+        for input in receivers {
+                let sender = sender.clone();
+                let remaining = remaining.clone();
+                thread::spawn(move || {
+                        while let Ok(item) = input.recv() {
+                                if sender.send(item).is_err() {
+                                        break;
+                                }
+                        }
+                        // the last forwarder to finish closes the output
+                        if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                                sender.close_with(CloseReason::Normal);
+                        }
+                });
+        }
+        receiver
+}
+
+/// Fan one receiver out to `n` receivers that each get a clone of every item.
+/// All outputs are closed once the source closes
+pub fn fan_out<T: Clone + Send + 'static>(rx: Receiver<T>, n: usize) -> Vec<Receiver<T>> {
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..n).map(|_| unbounded()).unzip();
+        thread::spawn(move || {
+                while let Ok(item) = rx.recv() {
+                        for sender in &senders {
+                                let _ = sender.send(item.clone());
+                        }
+                }
+                for sender in &senders {
+                        sender.close_with(CloseReason::Normal);
+                }
+        });
+        receivers
+}
+
+/// Move up to `max` queued items from `from` to `to`, in order, returning how
+/// many were moved. Never waits: both queues are locked for the whole move, and
+/// it stops early once `to` is full, leaving the rest in `from`. `to`'s overflow
+/// policy applies as it would for `try_send`.
+/// A closed `from` still hands over what it has left; `ChannelClosed` only
+/// comes back once it is closed and empty, and `to`'s error only if not a
+/// single item went through
+pub fn transfer<T>(from: &Receiver<T>, to: &Sender<T>, max: usize) -> Result<usize, ChannelError> {
+        if Arc::ptr_eq(&from.state, &to.state) {
+                return rotate(from, max);
+        }
+
+        // lock in address order, so transfers in opposite directions can't deadlock
+        let (mut from_elements, mut to_elements);
+        if Arc::as_ptr(&from.state) < Arc::as_ptr(&to.state) {
+                from_elements = from.state.elements.lock();
+                to_elements = to.state.elements.lock();
+        } else {
+                to_elements = to.state.elements.lock();
+                from_elements = from.state.elements.lock();
+        }
+        if from_elements.is_empty() && from.state.is_closed() {
+                return Err(ChannelError::ChannelClosed);
+        }
+
+        let mut moved = 0;
+        let mut pushed = 0;
+        let mut error = None;
+        while moved < max && !from_elements.is_empty() {
+                let room;
+                (to_elements, room) = match to.make_room(to_elements, false) {
+                        Ok(ready) => ready,
+                        Err(refused) => {
+                                error = Some(refused);
+                                break;
+                        }
+                };
+                let Some(item) = from_elements.pop_front() else {
+                        break;
+                };
+                moved += 1;
+                if !room {
+                        to.state.dropped(&item);
+                        continue;
+                }
+                to.state.observe_send(&item);
+                to_elements.push_back(item);
+                to.state.after_push(to_elements.len());
+                pushed += 1;
+        }
+
+        to.state.notify_receivers(pushed);
+        if moved > 0 {
+                from.state.is_full.notify_all();
+                from.state.notify_drained(from_elements.len());
+        }
+        match error {
+                Some(error) if moved == 0 => Err(error),
+                _ => Ok(moved),
+        }
+}
+
+// a transfer from a channel into itself moves items from the front to the back
+fn rotate<T>(channel: &Receiver<T>, max: usize) -> Result<usize, ChannelError> {
+        let mut elements = channel.state.elements.lock();
+        if channel.state.is_closed() {
+                return Err(ChannelError::ChannelClosed);
+        }
+        let moved = max.min(elements.len());
+        elements.rotate_left(moved);
+        Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::shared_state::bounded;
+
+        #[test]
+        fn test_merge_three_channels() {
+                let (mut tx1, rx1) = unbounded();
+                let (mut tx2, rx2) = unbounded();
+                let (mut tx3, rx3) = unbounded();
+                let merged = merge(vec![rx1, rx2, rx3]);
+
+                for i in 0..10 {
+                        tx1.send(i).unwrap();
+                        tx2.send(i + 10).unwrap();
+                        tx3.send(i + 20).unwrap();
+                }
+                tx1.close().unwrap();
+                tx2.close().unwrap();
+                tx3.close().unwrap();
+
+                let mut items = Vec::new();
+                while let Ok(item) = merged.recv() {
+                        items.push(item);
+                }
+                items.sort();
+                assert_eq!(items, (0..30).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_merge_nothing_is_closed() {
+                let merged = merge::<i32>(Vec::new());
+                assert!(matches!(merged.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_fan_out_identical_sequences() {
+                let (mut tx, rx) = unbounded();
+                let outputs = fan_out(rx, 3);
+                for i in 0..20 {
+                        tx.send(i).unwrap();
+                }
+                tx.close().unwrap();
+
+                for output in outputs {
+                        let mut items = Vec::new();
+                        while let Ok(item) = output.recv() {
+                                items.push(item);
+                        }
+                        assert_eq!(items, (0..20).collect::<Vec<_>>());
+                }
+        }
+
+        #[test]
+        fn test_transfer() {
+                let (mut from_tx, from) = unbounded();
+                let (to, to_rx) = bounded(3);
+                for i in 0..5 {
+                        from_tx.send(i).unwrap();
+                }
+                assert_eq!(transfer(&from, &to, 2).unwrap(), 2);
+                assert_eq!(to_rx.try_recv().unwrap(), 0);
+                assert_eq!(to_rx.try_recv().unwrap(), 1);
+
+                // a closed source still hands over what is left
+                from_tx.close().unwrap();
+                assert_eq!(transfer(&from, &to, 10).unwrap(), 3);
+                assert!(matches!(transfer(&from, &to, 10), Err(ChannelError::ChannelClosed)));
+                assert_eq!(to_rx.into_inner(), vec![2, 3, 4]);
+        }
+
+        #[test]
+        fn test_transfer_to_closed_keeps_items() {
+                let (from_tx, from) = unbounded();
+                let (mut to, _to_rx) = unbounded();
+                from_tx.send(1).unwrap();
+                from_tx.send(2).unwrap();
+                to.close().unwrap();
+                assert!(matches!(transfer(&from, &to, 10), Err(ChannelError::ChannelClosed)));
+                assert_eq!(from.try_recv().unwrap(), 1);
+                assert_eq!(from.try_recv().unwrap(), 2);
+        }
+
+        #[test]
+        fn test_transfer_stops_when_full() {
+                let (from_tx, from) = unbounded();
+                let (to, to_rx) = bounded(2);
+                for i in 0..5 {
+                        from_tx.send(i).unwrap();
+                }
+                assert_eq!(transfer(&from, &to, 5).unwrap(), 2);
+                assert!(matches!(transfer(&from, &to, 5), Err(ChannelError::Full)));
+                assert_eq!(from.state.elements.lock().len(), 3);
+                assert_eq!(to_rx.try_recv().unwrap(), 0);
+                assert_eq!(to_rx.try_recv().unwrap(), 1);
+        }
+
+        #[test]
+        fn test_opposite_transfers_dont_deadlock() {
+                let (a_tx, a_rx) = unbounded();
+                let (b_tx, b_rx) = unbounded();
+                for i in 0..100 {
+                        a_tx.send(i).unwrap();
+                        b_tx.send(i).unwrap();
+                }
+                thread::scope(|s| {
+                        s.spawn(|| {
+                                for _ in 0..1000 {
+                                        let _ = transfer(&a_rx, &b_tx, 3);
+                                }
+                        });
+                        for _ in 0..1000 {
+                                let _ = transfer(&b_rx, &a_tx, 3);
+                        }
+                });
+                assert_eq!(a_rx.state.elements.lock().len() + b_rx.state.elements.lock().len(), 200);
+        }
+}
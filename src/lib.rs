@@ -1,4 +1,23 @@
+pub mod sync;
 pub mod shared_state;
 pub mod sender;
 pub mod receiver;
+pub mod queue;
+pub mod ttl;
+pub mod combinators;
+pub mod ring;
+pub mod coalescing;
+pub mod request;
+pub mod weighted;
+pub mod priority;
+pub mod bytes;
+pub mod seq;
+pub mod signal;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "futures")]
+pub mod stream;
 
+pub use shared_state::{bounded, unbounded, ChannelError, SendError};
+pub use sender::Sender;
+pub use receiver::Receiver;
@@ -0,0 +1,80 @@
+use crate::shared_state::{channel_with_queue, SendError};
+use crate::queue::Queue;
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::collections::VecDeque;
+
+/// Two FIFO lanes, where the high priority lane is always drained first
+pub struct DualLane<T> {
+        pub high: VecDeque<T>,
+        pub normal: VecDeque<T>,
+}
+
+impl<T> Default for DualLane<T> {
+        fn default() -> Self {
+                DualLane { high: VecDeque::new(), normal: VecDeque::new() }
+        }
+}
+
+impl<T> Queue<T> for DualLane<T> {
+        fn push_back(&mut self, item: T) {
+                self.normal.push_back(item);
+        }
+
+        fn pop_front(&mut self) -> Option<T> {
+                self.high.pop_front().or_else(|| self.normal.pop_front())
+        }
+
+        fn len(&self) -> usize {
+                self.high.len() + self.normal.len()
+        }
+
+        fn is_empty(&self) -> bool {
+                self.high.is_empty() && self.normal.is_empty()
+        }
+}
+
+/// Sender part of a dual priority channel
+pub type DualPrioritySender<T> = Sender<T, DualLane<T>>;
+
+/// Receiver part of a dual priority channel
+pub type DualPriorityReceiver<T> = Receiver<T, DualLane<T>>;
+
+/// Create a channel with a normal and a high priority lane. `send` goes to the
+/// normal lane, `send_priority` to the high one, and receivers only see normal
+/// items once no high priority item is queued
+pub fn dual_priority_channel<T>() -> (DualPrioritySender<T>, DualPriorityReceiver<T>) {
+        channel_with_queue(DualLane::default())
+}
+
+impl<T> Sender<T, DualLane<T>> {
+        /// Send an item on the high priority lane, ahead of every normal item
+        pub fn send_priority(&self, item: T) -> Result<(), SendError<T>> {
+                self.push(item, true, |lanes, item| lanes.high.push_back(item))
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::shared_state::ChannelError;
+
+        #[test]
+        fn test_priority_items_first() {
+                let (tx, rx) = dual_priority_channel();
+                tx.send("bulk 1").unwrap();
+                tx.send_priority("urgent 1").unwrap();
+                tx.send("bulk 2").unwrap();
+                tx.send_priority("urgent 2").unwrap();
+                assert_eq!(rx.recv().unwrap(), "urgent 1");
+                tx.send_priority("urgent 3").unwrap();
+
+                let mut order = Vec::new();
+                while let Ok(item) = rx.try_recv() {
+                        order.push(item);
+                }
+                assert_eq!(order, vec!["urgent 2", "urgent 3", "bulk 1", "bulk 2"]);
+                drop(tx);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+}
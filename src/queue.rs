@@ -0,0 +1,33 @@
+use std::collections::VecDeque;
+
+/// Storage backing a channel. Every operation runs with the channel lock held
+pub trait Queue<T> {
+        fn push_back(&mut self, item: T);
+        fn pop_front(&mut self) -> Option<T>;
+        fn len(&self) -> usize;
+        fn is_empty(&self) -> bool;
+
+        /// Most items the queue can hold, or `None` if it can grow without bound.
+        /// `push_back` is only called while there is room
+        fn capacity(&self) -> Option<usize> {
+                None
+        }
+}
+
+impl<T> Queue<T> for VecDeque<T> {
+        fn push_back(&mut self, item: T) {
+                VecDeque::push_back(self, item)
+        }
+
+        fn pop_front(&mut self) -> Option<T> {
+                VecDeque::pop_front(self)
+        }
+
+        fn len(&self) -> usize {
+                VecDeque::len(self)
+        }
+
+        fn is_empty(&self) -> bool {
+                VecDeque::is_empty(self)
+        }
+}
@@ -1,48 +1,494 @@
-use crate::shared_state::{SharedState, ChannelError};
+use crate::shared_state::{SharedState, ChannelError, ChannelStatus, CloseReason, YieldPoint};
+use crate::queue::Queue;
+use crate::sync::MutexGuard;
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex, Condvar};
-use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
 
+/// How often a blocked `recv_interruptible` wakes up to check its cancel flag
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
+/// Most `spin_loop` hints between two checks of the queue while spinning
+const MAX_SPIN_BACKOFF: u32 = 64;
+
+/// How long a round-robin receiver that was served last leaves an item for the others
+const ROUND_ROBIN_DEFER: Duration = Duration::from_micros(50);
 
 /// Receiver part of the channel
-pub struct Receiver<T> {
-        pub state: Arc<SharedState<T>>
+pub struct Receiver<T, Q = VecDeque<T>> {
+        pub state: Arc<SharedState<T, Q>>,
+        pub id: usize,
 }
 
-impl<T> Receiver<T> {
+impl<T, Q: Queue<T>> Receiver<T, Q> {
         pub fn recv(&self) -> Result<T, ChannelError> {
-                let (mut elements, condvar) = (self.state.elements.lock().unwrap(), &self.state.is_empty);
-                while elements.is_empty() {
-                        if self.state.closed.load(Ordering::Relaxed) {
+                let mut elements = self.wait_for_items()?;
+                // we have an element, return it
+                Ok(self.pop(&mut elements).unwrap())
+        }
+
+        /// Like `recv`, but returns `None` once the channel is closed and drained,
+        /// for `while let Some(item) = rx.recv_opt()` loops
+        pub fn recv_opt(&self) -> Option<T> {
+                self.recv().ok()
+        }
+
+        /// Like `recv`, but also returns how long it took to get the item, lock
+        /// acquisition and waiting for data included. Only this variant pays for
+        /// the timestamps
+        pub fn recv_timed(&self) -> Result<(T, Duration), ChannelError> {
+                let start = Instant::now();
+                let item = self.recv()?;
+                Ok((item, start.elapsed()))
+        }
+
+        /// Like `recv`, but also returns how many items are still queued after
+        /// popping, read under the same lock
+        pub fn recv_with_depth(&self) -> Result<(T, usize), ChannelError> {
+                let mut elements = self.wait_for_items()?;
+                let item = self.pop(&mut elements).unwrap();
+                Ok((item, elements.len()))
+        }
+
+        // pop the front element, letting a sender blocked on a full channel know there is room
+        pub(crate) fn pop(&self, elements: &mut Q) -> Option<T> {
+                let item = elements.pop_front();
+                if item.is_some() {
+                        if self.state.capacity.is_some() {
+                                self.state.is_full.notify_one();
+                        }
+                        if self.state.round_robin {
+                                self.state.last_served.store(self.id, Ordering::Relaxed);
+                        }
+                        self.state.notify_drained(elements.len());
+                }
+                item
+        }
+
+        // block until the queue has at least one element and return the locked queue
+        pub(crate) fn wait_for_items(&self) -> Result<MutexGuard<'_, Q>, ChannelError> {
+                if let Some(elements) = self.spin_for_items() {
+                        return Ok(elements);
+                }
+                let mut elements = self.state.elements.lock();
+                let mut deferred = false;
+                loop {
+                        while elements.is_empty() {
+                                if let Some(error) = self.state.empty_error() {
+                                        return Err(error);
+                                }
+                                elements = self.wait(elements);
+                        }
+                        if deferred || !self.should_defer() {
+                                return Ok(elements);
+                        }
+                        // we were served last, give an idle receiver the first shot at this one
+                        deferred = true;
+                        self.state.is_empty.notify_one();
+                        elements = self.park(elements, Some(ROUND_ROBIN_DEFER));
+                }
+        }
+
+        // whether round-robin mode wants this receiver to leave the front item to another
+        fn should_defer(&self) -> bool {
+                self.state.round_robin
+                        && self.state.last_served.load(Ordering::Relaxed) == self.id
+                        && self.state.waiting_receivers.load(Ordering::Relaxed) > 0
+        }
+
+        // spin for a bounded number of rounds before parking, backing off exponentially,
+        // in the hope that a burst refills the queue without paying for a wakeup
+        fn spin_for_items(&self) -> Option<MutexGuard<'_, Q>> {
+                let mut backoff = 1;
+                for _ in 0..self.state.spins {
+                        if let Some(elements) = self.state.elements.try_lock() {
+                                if !elements.is_empty() {
+                                        return Some(elements);
+                                }
+                        }
+                        for _ in 0..backoff {
+                                std::hint::spin_loop();
+                        }
+                        backoff = (backoff * 2).min(MAX_SPIN_BACKOFF);
+                }
+                None
+        }
+
+        // park until a sender signals, waking in time to notice a deadline passing
+        fn wait<'a>(&self, elements: MutexGuard<'a, Q>) -> MutexGuard<'a, Q> {
+                self.state.yield_point(YieldPoint::RecvWait);
+                let timeout = self.state.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                self.park(elements, timeout)
+        }
+
+        // park on the empty condvar for at most `timeout`, counted in `waiting_count`
+        pub(crate) fn park<'a>(&self, elements: MutexGuard<'a, Q>, timeout: Option<Duration>) -> MutexGuard<'a, Q> {
+                self.state.waiting_receivers.fetch_add(1, Ordering::Relaxed);
+                let elements = match timeout {
+                        Some(timeout) => self.state.is_empty.wait_timeout(elements, timeout),
+                        None => self.state.is_empty.wait(elements),
+                };
+                self.state.waiting_receivers.fetch_sub(1, Ordering::Relaxed);
+                elements
+        }
+
+        /// Like `recv`, but gives up with `Cancelled` once `cancel` is set. The flag is
+        /// polled every few milliseconds, so the channel stays open for other consumers
+        pub fn recv_interruptible(&self, cancel: &Arc<AtomicBool>) -> Result<T, ChannelError> {
+                let mut elements = self.state.elements.lock();
+                loop {
+                        if cancel.load(Ordering::Relaxed) {
+                                return Err(ChannelError::Cancelled);
+                        }
+                        if let Some(item) = self.pop(&mut elements) {
+                                return Ok(item);
+                        }
+                        if let Some(error) = self.state.empty_error() {
+                                return Err(error);
+                        }
+                        elements = self.park(elements, Some(CANCEL_POLL_INTERVAL));
+                }
+        }
+
+        /// Block until at least `min` items are queued or `timeout` passes, then drain
+        /// everything queued under one lock. Returns `Timeout` if nothing arrived, or
+        /// `ChannelClosed` if the channel closed empty
+        pub fn recv_min(&self, min: usize, timeout: Duration) -> Result<Vec<T>, ChannelError> {
+                let deadline = Instant::now() + timeout;
+                let mut elements = self.state.elements.lock();
+                loop {
+                        if elements.len() >= min.max(1) || self.state.empty_error().is_some() {
+                                break;
+                        }
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                                break;
+                        }
+                        elements = self.park(elements, Some(remaining));
+                }
+
+                if elements.is_empty() {
+                        return Err(self.state.empty_error().unwrap_or(ChannelError::Timeout));
+                }
+                let items = drain(&mut *elements);
+                self.state.is_full.notify_all();
+                self.state.notify_drained(elements.len());
+                Ok(items)
+        }
+
+        /// Iterate over the channel in chunks of up to `size` items. Each chunk blocks
+        /// for one item, then takes whatever else is already queued, so the chunk left
+        /// when the channel closes is still yielded before the iterator ends
+        pub fn iter_chunks(&self, size: usize) -> impl Iterator<Item = Vec<T>> + '_ {
+                let size = size.max(1);
+                std::iter::from_fn(move || {
+                        let mut elements = self.wait_for_items().ok()?;
+                        let mut chunk = Vec::with_capacity(size.min(elements.len()));
+                        while chunk.len() < size {
+                                let Some(item) = elements.pop_front() else { break };
+                                chunk.push(item);
+                        }
+                        if self.state.capacity.is_some() {
+                                self.state.is_full.notify_all();
+                        }
+                        self.state.notify_drained(elements.len());
+                        Some(chunk)
+                })
+        }
+
+        /// Block for the first item, then keep collecting until `max` items are queued
+        /// or `timeout` has passed since the first one arrived, and take up to `max`
+        /// of them under one lock. Returns `Timeout` if nothing arrived within
+        /// `timeout`, or `ChannelClosed` if the channel closed empty. If another
+        /// receiver takes everything during the window, this goes back to waiting
+        /// for a first item, until `timeout` from the call has passed
+        pub fn recv_batch(&self, max: usize, timeout: Duration) -> Result<Vec<T>, ChannelError> {
+                let max = max.max(1);
+                let mut elements = self.state.elements.lock();
+                let first_deadline = Instant::now() + timeout;
+                loop {
+                        while elements.is_empty() {
+                                if let Some(error) = self.state.empty_error() {
+                                        return Err(error);
+                                }
+                                let remaining = first_deadline.saturating_duration_since(Instant::now());
+                                if remaining.is_zero() {
+                                        return Err(ChannelError::Timeout);
+                                }
+                                elements = self.park(elements, Some(remaining));
+                        }
+
+                        // the batch window opens with the first item
+                        let deadline = Instant::now() + timeout;
+                        while elements.len() < max && self.state.empty_error().is_none() {
+                                let remaining = deadline.saturating_duration_since(Instant::now());
+                                if remaining.is_zero() {
+                                        break;
+                                }
+                                elements = self.park(elements, Some(remaining));
+                        }
+                        if !elements.is_empty() {
+                                break;
+                        }
+                }
+
+                let mut items = Vec::with_capacity(max.min(elements.len()));
+                while items.len() < max {
+                        let Some(item) = elements.pop_front() else { break };
+                        items.push(item);
+                }
+                if self.state.capacity.is_some() {
+                        self.state.is_full.notify_all();
+                }
+                self.state.notify_drained(elements.len());
+                Ok(items)
+        }
+
+        /// Block until at least `n` senders exist, counting clones as they are made.
+        /// Returns `Timeout` if they don't all show up in time, or `ChannelClosed` if
+        /// the channel closes first
+        pub fn wait_for_senders(&self, n: usize, timeout: Duration) -> Result<(), ChannelError> {
+                let deadline = Instant::now() + timeout;
+                let mut elements = self.state.elements.lock();
+                while self.state.num_senders.load(Ordering::Relaxed) < n {
+                        if self.state.is_closed() {
                                 return Err(ChannelError::ChannelClosed);
                         }
-                        elements = condvar.wait(elements).unwrap();
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                                return Err(ChannelError::Timeout);
+                        }
+                        elements = self.state.senders_joined.wait_timeout(elements, remaining);
                 }
-                // we have an element, return it
-                Ok(elements.pop_front().unwrap())
+                Ok(())
         }
 
+        /// Receive an item without blocking. Buffered items are always handed out
+        /// before a closed or poisoned channel reports it
         pub fn try_recv(&self) -> Result<T, ChannelError> {
-                if self.state.closed.load(Ordering::Acquire) {
-                    if let Ok(mut guard) = self.state.elements.try_lock() {
-                        return if let Some(item) = guard.pop_front() {
-                            Ok(item)
-                        } else {
-                            Err(ChannelError::ChannelClosed)
-                        };
-                    }
-                }
-                
-                if let Ok(mut guard) = self.state.elements.try_lock() {
-                    if let Some(item) = guard.pop_front() {
+                let Some(mut elements) = self.state.elements.try_lock() else {
+                        return Err(ChannelError::RecvBlocked);
+                };
+                if let Some(item) = self.pop(&mut elements) {
                         Ok(item)
-                    } else {
-                        Err(ChannelError::ChannelEmpty)
-                    }
                 } else {
-                    Err(ChannelError::RecvBlocked)
+                        Err(self.state.empty_error().unwrap_or(ChannelError::ChannelEmpty))
                 }
-            }
-}
\ No newline at end of file
+        }
+
+        /// Like `try_recv`, but willing to wait up to `lock_timeout` to acquire the lock.
+        /// Never waits for data: an empty queue returns `ChannelEmpty` right away
+        pub fn poll_recv(&self, lock_timeout: Duration) -> Result<T, ChannelError> {
+                let deadline = Instant::now() + lock_timeout;
+                let mut elements = loop {
+                        if let Some(guard) = self.state.elements.try_lock() {
+                                break guard;
+                        }
+                        if Instant::now() >= deadline {
+                                return Err(ChannelError::RecvBlocked);
+                        }
+                        thread::yield_now();
+                };
+
+                if let Some(item) = self.pop(&mut elements) {
+                        Ok(item)
+                } else {
+                        Err(self.state.empty_error().unwrap_or(ChannelError::ChannelEmpty))
+                }
+        }
+
+        /// Consume the receiver and hand back everything still queued.
+        ///
+        /// If this is the last handle to the channel the queue is unwrapped without
+        /// locking. Otherwise senders are still alive, so the queue is drained under
+        /// the lock and the channel stays usable for them; nothing here panics
+        pub fn into_inner(self) -> Vec<T> {
+                let state = self.state.clone();
+                drop(self);
+                match Arc::try_unwrap(state) {
+                        Ok(state) => drain(&mut state.elements.into_inner()),
+                        Err(state) => {
+                                let mut elements = state.elements.lock();
+                                let items = drain(&mut *elements);
+                                state.is_full.notify_all();
+                                state.notify_drained(elements.len());
+                                items
+                        }
+                }
+        }
+
+        /// Snapshot of whether the channel is closed and how much is left to drain,
+        /// read under a single lock
+        pub fn status(&self) -> ChannelStatus {
+                let elements = self.state.elements.lock();
+                match (self.state.is_closed(), elements.len()) {
+                        (false, _) => ChannelStatus::Open,
+                        (true, 0) => ChannelStatus::ClosedEmpty,
+                        (true, len) => ChannelStatus::ClosedNonEmpty(len),
+                }
+        }
+
+        /// How many receivers are parked waiting for an item right now. Together with
+        /// the queue length this tells a stuck consumer apart from a slow producer
+        pub fn waiting_count(&self) -> usize {
+                self.state.waiting_receivers.load(Ordering::Relaxed)
+        }
+
+        /// The most items ever queued at once over the channel's lifetime
+        pub fn high_water_mark(&self) -> usize {
+                self.state.max_len.load(Ordering::Relaxed)
+        }
+
+        /// The reason the channel was closed, if one was given
+        pub fn close_reason(&self) -> Option<CloseReason> {
+                self.state.close_reason.lock().clone()
+        }
+}
+
+impl<T> Receiver<T> {
+        /// Pop an item but hold on to it in a guard, so it can go back to the front of
+        /// the queue with `requeue` if processing it fails. Dropping the guard
+        /// otherwise just drops the item
+        pub fn recv_guard(&self) -> Result<RecvGuard<'_, T>, ChannelError> {
+                let item = self.recv()?;
+                Ok(RecvGuard { receiver: self, item: Some(item), requeue: false })
+        }
+
+        /// Without blocking, run `f` on the front item. If `f` returns `(true, r)` the
+        /// item is popped, otherwise it stays queued; `r` is returned either way
+        pub fn try_peek_decide<R>(&self, f: impl FnOnce(&T) -> (bool, R)) -> Result<R, ChannelError> {
+                let Some(mut elements) = self.state.elements.try_lock() else {
+                        return Err(ChannelError::RecvBlocked);
+                };
+                let Some(front) = elements.front() else {
+                        return Err(self.state.empty_error().unwrap_or(ChannelError::ChannelEmpty));
+                };
+                let (consume, result) = self.state.run_hook(|| f(front));
+                if consume {
+                        self.pop(&mut elements);
+                }
+                Ok(result)
+        }
+
+        /// Swap the whole queue out for an empty one and return it, in O(1). Handy
+        /// for double buffering. A channel with nothing queued, closed or not, just
+        /// gives back an empty queue
+        pub fn take_all(&self) -> VecDeque<T> {
+                let mut elements = self.state.elements.lock();
+                let items = std::mem::take(&mut *elements);
+                if !items.is_empty() {
+                        self.state.is_full.notify_all();
+                        self.state.notify_drained(elements.len());
+                }
+                items
+        }
+
+        /// Block for an item, then pop items off the front for as long as `pred`
+        /// holds, under one lock. Only the run at the front is looked at: the first
+        /// item that fails `pred` stays queued, along with everything behind it, so
+        /// order is preserved. The run is empty if the front item already fails
+        pub fn recv_while(&self, pred: impl Fn(&T) -> bool) -> Result<Vec<T>, ChannelError> {
+                let mut elements = self.wait_for_items()?;
+                let mut run = Vec::new();
+                while self.state.run_hook(|| elements.front().is_some_and(&pred)) {
+                        run.extend(self.pop(&mut elements));
+                }
+                Ok(run)
+        }
+
+        /// Block until some queued item satisfies `pred`, then remove and return the
+        /// first such item. Items that don't match stay queued in order.
+        ///
+        /// Every wakeup rescans the whole queue, so this is O(n) per scan, and there
+        /// is no fairness: an item nobody matches can sit in the queue forever
+        pub fn recv_matching(&self, pred: impl Fn(&T) -> bool) -> Result<T, ChannelError> {
+                let mut elements = self.state.elements.lock();
+                loop {
+                        if let Some(index) = self.state.run_hook(|| elements.iter().position(&pred)) {
+                                let item = elements.remove(index).unwrap();
+                                if self.state.capacity.is_some() {
+                                        self.state.is_full.notify_one();
+                                }
+                                self.state.notify_drained(elements.len());
+                                return Ok(item);
+                        }
+                        if let Some(error) = self.state.empty_error() {
+                                return Err(error);
+                        }
+                        elements = self.wait(elements);
+                }
+        }
+}
+
+/// An item taken off the channel that can still be handed back, see `Receiver::recv_guard`
+pub struct RecvGuard<'a, T> {
+        receiver: &'a Receiver<T>,
+        item: Option<T>,
+        requeue: bool,
+}
+
+impl<T> RecvGuard<'_, T> {
+        pub fn item(&self) -> &T {
+                self.item.as_ref().unwrap()
+        }
+
+        /// Put the item back at the front of the queue, so it is the next one received.
+        /// This never waits, even if a bounded channel has filled up again meanwhile
+        pub fn requeue(mut self) {
+                self.requeue = true;
+        }
+}
+
+impl<T> Drop for RecvGuard<'_, T> {
+        fn drop(&mut self) {
+                let Some(item) = self.item.take() else {
+                        return;
+                };
+                if self.requeue {
+                        let state = &self.receiver.state;
+                        let mut elements = state.elements.lock();
+                        elements.push_front(item);
+                        state.after_push(elements.len());
+                        state.notify_receivers(1);
+                }
+        }
+}
+
+impl<T, Q> Clone for Receiver<T, Q> {
+        fn clone(&self) -> Self {
+                self.state.num_receivers.fetch_add(1, Ordering::Relaxed);
+                let id = self.state.next_receiver_id.fetch_add(1, Ordering::Relaxed);
+                Receiver { state: self.state.clone(), id }
+        }
+}
+
+impl<T, Q> Drop for Receiver<T, Q> {
+        fn drop(&mut self) {
+                if self.state.num_receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
+                        // wake senders blocked on a full channel, or on the queue draining,
+                        // so they see nobody is left
+                        let _elements = self.state.elements.lock();
+                        self.state.no_receivers.store(true, Ordering::Relaxed);
+                        self.state.is_full.notify_all();
+                        self.state.drained.notify_all();
+                }
+        }
+}
+
+// pop everything out of the queue, front to back
+fn drain<T, Q: Queue<T>>(queue: &mut Q) -> Vec<T> {
+        let mut items = Vec::with_capacity(queue.len());
+        while let Some(item) = queue.pop_front() {
+                items.push(item);
+        }
+        items
+}
+
+impl<T, Q: Queue<T>> fmt::Debug for Receiver<T, Q> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.state.fmt_debug(f, "Receiver")
+        }
+}
@@ -0,0 +1,72 @@
+use crate::shared_state::{unbounded, ChannelError, SendError};
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+
+/// A request along with the channel its reply goes back on
+pub struct Request<Req, Resp> {
+        pub payload: Req,
+        reply: Sender<Resp>,
+}
+
+impl<Req, Resp> Request<Req, Resp> {
+        /// Send the reply back to the waiting requester
+        pub fn respond(self, response: Resp) -> Result<(), SendError<Resp>> {
+                self.reply.send(response)
+        }
+}
+
+/// Sender part of a request channel
+pub type RequestSender<Req, Resp> = Sender<Request<Req, Resp>>;
+
+/// Receiver part of a request channel
+pub type RequestReceiver<Req, Resp> = Receiver<Request<Req, Resp>>;
+
+/// Create a channel for request/reply flows, where each request carries its own
+/// one-shot reply channel
+pub fn request_channel<Req, Resp>() -> (RequestSender<Req, Resp>, RequestReceiver<Req, Resp>) {
+        unbounded()
+}
+
+impl<Req, Resp> Sender<Request<Req, Resp>> {
+        /// Send a request and block until it is answered. Returns `ChannelClosed`
+        /// if the request channel is closed, or if the request is dropped unanswered
+        pub fn send_and_wait(&self, payload: Req) -> Result<Resp, ChannelError> {
+                let (reply, response) = unbounded();
+                self.send(Request { payload, reply })?;
+                response.recv()
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use std::thread;
+
+        #[test]
+        fn test_send_and_wait() {
+                let (tx, rx) = request_channel::<i32, i32>();
+                let server = thread::spawn(move || {
+                        while let Ok(request) = rx.recv() {
+                                let doubled = request.payload * 2;
+                                request.respond(doubled).unwrap();
+                        }
+                });
+                let tx2 = tx.clone();
+                assert_eq!(tx.send_and_wait(2).unwrap(), 4);
+                assert_eq!(tx2.send_and_wait(21).unwrap(), 42);
+                drop(tx);
+                drop(tx2);
+                server.join().unwrap();
+        }
+
+        #[test]
+        fn test_unanswered_request() {
+                let (tx, rx) = request_channel::<i32, i32>();
+                let server = thread::spawn(move || {
+                        // drop the request without responding
+                        let _ = rx.recv();
+                });
+                assert!(matches!(tx.send_and_wait(1), Err(ChannelError::ChannelClosed)));
+                server.join().unwrap();
+        }
+}
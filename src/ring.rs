@@ -0,0 +1,164 @@
+use crate::shared_state::channel_with_queue;
+use crate::queue::Queue;
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::mem::MaybeUninit;
+
+/// Fixed capacity FIFO backed by a single allocation
+pub struct RingBuffer<T> {
+        slots: Box<[MaybeUninit<T>]>,
+        head: usize,
+        len: usize,
+}
+
+impl<T> RingBuffer<T> {
+        /// Create a ring buffer that holds at most `capacity` items
+        pub fn with_capacity(capacity: usize) -> Self {
+                assert!(capacity > 0, "ring buffer capacity must be non-zero");
+                RingBuffer {
+                        slots: (0..capacity).map(|_| MaybeUninit::uninit()).collect(),
+                        head: 0,
+                        len: 0,
+                }
+        }
+
+        /// Push an item to the back, handing it back if the buffer is full
+        pub fn push_back(&mut self, item: T) -> Result<(), T> {
+                if self.is_full() {
+                        return Err(item);
+                }
+                let tail = (self.head + self.len) % self.capacity();
+                self.slots[tail].write(item);
+                self.len += 1;
+                Ok(())
+        }
+
+        /// Pop the item at the front
+        pub fn pop_front(&mut self) -> Option<T> {
+                if self.is_empty() {
+                        return None;
+                }
+                // SAFETY: the `len` slots starting at `head` are always initialized
+                let item = unsafe { self.slots[self.head].assume_init_read() };
+                self.head = (self.head + 1) % self.capacity();
+                self.len -= 1;
+                Some(item)
+        }
+
+        pub fn len(&self) -> usize {
+                self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+                self.len == 0
+        }
+
+        pub fn is_full(&self) -> bool {
+                self.len == self.capacity()
+        }
+
+        pub fn capacity(&self) -> usize {
+                self.slots.len()
+        }
+}
+
+impl<T> Drop for RingBuffer<T> {
+        fn drop(&mut self) {
+                while self.pop_front().is_some() {}
+        }
+}
+
+impl<T> Queue<T> for RingBuffer<T> {
+        fn push_back(&mut self, item: T) {
+                if RingBuffer::push_back(self, item).is_err() {
+                        panic!("pushed to a full ring buffer");
+                }
+        }
+
+        fn pop_front(&mut self) -> Option<T> {
+                RingBuffer::pop_front(self)
+        }
+
+        fn len(&self) -> usize {
+                RingBuffer::len(self)
+        }
+
+        fn is_empty(&self) -> bool {
+                RingBuffer::is_empty(self)
+        }
+
+        fn capacity(&self) -> Option<usize> {
+                Some(RingBuffer::capacity(self))
+        }
+}
+
+/// Sender part of a ring channel
+pub type RingSender<T> = Sender<T, RingBuffer<T>>;
+
+/// Receiver part of a ring channel
+pub type RingReceiver<T> = Receiver<T, RingBuffer<T>>;
+
+/// Create a bounded channel backed by a fixed size ring buffer
+pub fn ring_channel<T>(capacity: usize) -> (RingSender<T>, RingReceiver<T>) {
+        channel_with_queue(RingBuffer::with_capacity(capacity))
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::shared_state::ChannelError;
+        use crate::testing::DropTally;
+        use std::thread;
+
+        #[test]
+        fn test_wraparound() {
+                let mut ring = RingBuffer::with_capacity(3);
+                for i in 0..100 {
+                        ring.push_back(i).unwrap();
+                        ring.push_back(i + 1000).unwrap();
+                        assert_eq!(ring.pop_front(), Some(i));
+                        assert_eq!(ring.pop_front(), Some(i + 1000));
+                }
+                assert!(ring.is_empty());
+        }
+
+        #[test]
+        fn test_push_full_returns_item() {
+                let mut ring = RingBuffer::with_capacity(2);
+                ring.push_back(1).unwrap();
+                ring.push_back(2).unwrap();
+                assert_eq!(ring.push_back(3), Err(3));
+        }
+
+        #[test]
+        fn test_drop_remaining_items() {
+                let mut tally = DropTally::default();
+                let mut ring = RingBuffer::with_capacity(4);
+                // move the head around before leaving items behind
+                for _ in 0..6 {
+                        ring.push_back(tally.item()).ok().unwrap();
+                        ring.pop_front();
+                }
+                for _ in 0..3 {
+                        ring.push_back(tally.item()).ok().unwrap();
+                }
+                assert_eq!(tally.dropped(), 6);
+                drop(ring);
+                tally.assert_no_leaks();
+        }
+
+        #[test]
+        fn test_ring_channel_blocks_when_full() {
+                let (tx, rx) = ring_channel(4);
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..1000 {
+                                tx.send(i).unwrap();
+                        }
+                });
+                for i in 0..1000 {
+                        assert_eq!(rx.recv().unwrap(), i);
+                }
+                tx_thread.join().unwrap();
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+}
@@ -1,38 +1,237 @@
-use crate::shared_state::{SharedState, ChannelError};
+use crate::shared_state::{SharedState, ChannelError, CloseReason, OverflowPolicy, SendError, YieldPoint};
+use crate::queue::Queue;
+use crate::sync::MutexGuard;
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex, Condvar};
-use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// First and longest sleeps between attempts in `send_blocking_backoff`
+const MIN_SEND_BACKOFF: Duration = Duration::from_micros(10);
+const MAX_SEND_BACKOFF: Duration = Duration::from_millis(10);
+
 /// Sender part of the channel
-pub struct Sender<T> {
-        pub state: Arc<SharedState<T>>
+///
+/// `Sender<T>` is `Send + Sync` exactly when `T: Send`, so a channel of
+/// non-`Send` items such as `Rc` cannot be moved across threads:
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// let (tx, _rx) = buffy::shared_state::unbounded::<Rc<i32>>();
+/// std::thread::spawn(move || drop(tx));
+/// ```
+pub struct Sender<T, Q = VecDeque<T>> {
+        pub state: Arc<SharedState<T, Q>>
 }
 
-impl<T> Sender<T> {
-        /// Send an item on the channel
-        pub fn send(&self, item: T) -> Result<(), ChannelError> {
-                let mut elements = self.state.elements.lock().unwrap();
+impl<T, Q: Queue<T>> Sender<T, Q> {
+        /// Send an item on the channel, handing it back if the channel is closed,
+        /// poisoned, or every receiver is gone
+        pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+                self.push(item, true, Q::push_back)
+        }
+
+        /// Send an item without waiting for room. A full bounded channel that
+        /// blocks on overflow hands the item back with `Full`
+        pub fn try_send(&self, item: T) -> Result<(), SendError<T>> {
+                self.push(item, false, Q::push_back)
+        }
+
+        // push a single item with `place`, waiting for room only if `block` is set
+        pub(crate) fn push(&self, item: T, block: bool, place: impl FnOnce(&mut Q, T)) -> Result<(), SendError<T>> {
+                let elements = self.state.elements.lock();
+                self.state.yield_point(YieldPoint::Send);
+
+                let (mut elements, room) = match self.make_room(elements, block) {
+                        Ok(ready) => ready,
+                        Err(error) => return Err(SendError { item, error }),
+                };
+                if !room {
+                        self.state.dropped(&item);
+                        return Ok(());
+                }
+
+                self.state.observe_send(&item);
+                place(&mut elements, item);
+                self.state.after_push(elements.len());
+                self.state.notify_receivers(1);
+                Ok(())
+        }
 
-                if self.state.closed.load(Ordering::Relaxed) {
-                        return Err(ChannelError::ChannelClosed);
+        /// Keep retrying `try_send` with exponential backoff until the item fits or
+        /// `max_wait` has passed, sleeping between attempts rather than parking on the
+        /// channel. Gives the item back with `Timeout` if it never fit
+        pub fn send_blocking_backoff(&self, item: T, max_wait: Duration) -> Result<(), SendError<T>> {
+                let deadline = Instant::now() + max_wait;
+                let mut backoff = MIN_SEND_BACKOFF;
+                let mut item = item;
+                loop {
+                        match self.try_send(item) {
+                                Err(SendError { item: rejected, error: ChannelError::Full }) => item = rejected,
+                                result => return result,
+                        }
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                                return Err(SendError { item, error: ChannelError::Timeout });
+                        }
+                        thread::sleep(backoff.min(remaining));
+                        backoff = (backoff * 2).min(MAX_SEND_BACKOFF);
                 }
+        }
 
-                elements.push_back(item);
-                self.state.is_empty.notify_one();
+        /// Send every item under a single lock, blocking for room like `send`.
+        /// Wakes all parked receivers, since there may be work for each of them.
+        /// On failure the items not yet sent are handed back
+        pub fn send_batch(&self, items: Vec<T>) -> Result<(), SendError<Vec<T>>> {
+                let mut elements = self.state.elements.lock();
+                let mut items = items.into_iter();
+                let mut pushed = 0;
+                while let Some(item) = items.next() {
+                        let room;
+                        (elements, room) = match self.make_room(elements, true) {
+                                Ok(ready) => ready,
+                                Err(error) => {
+                                        self.state.notify_receivers(pushed);
+                                        let item = std::iter::once(item).chain(items).collect();
+                                        return Err(SendError { item, error });
+                                }
+                        };
+                        if !room {
+                                self.state.dropped(&item);
+                                continue;
+                        }
+                        self.state.observe_send(&item);
+                        elements.push_back(item);
+                        self.state.after_push(elements.len());
+                        pushed += 1;
+                }
+                self.state.notify_receivers(pushed);
                 Ok(())
         }
 
+        // wait until the queue can take one more item, following the overflow policy,
+        // or fail with `Full` instead of waiting when `block` is false.
+        // Returns false alongside the lock when the policy says to drop the new item
+        pub(crate) fn make_room<'a>(&self, mut elements: MutexGuard<'a, Q>, block: bool) -> Result<(MutexGuard<'a, Q>, bool), ChannelError> {
+                loop {
+                        // receivers stop at a poisoned channel, so nothing would read the item
+                        if self.state.is_poisoned() {
+                                return Err(ChannelError::Poisoned);
+                        }
+                        if self.state.is_closed() {
+                                return Err(ChannelError::ChannelClosed);
+                        }
+                        if self.state.no_receivers.load(Ordering::Relaxed) {
+                                return Err(ChannelError::NoReceivers);
+                        }
+                        if self.state.has_room(elements.len()) {
+                                return Ok((elements, true));
+                        }
+                        // bounded and full, make room according to the overflow policy
+                        match self.state.overflow {
+                                OverflowPolicy::Block if !block => return Err(ChannelError::Full),
+                                OverflowPolicy::Block => {
+                                        // a batch may have queued items nobody was told about yet
+                                        self.state.notify_receivers(elements.len());
+                                        elements = self.state.is_full.wait(elements);
+                                }
+                                OverflowPolicy::DropOldest => {
+                                        if let Some(oldest) = elements.pop_front() {
+                                                self.state.dropped(&oldest);
+                                        }
+                                        return Ok((elements, true));
+                                }
+                                OverflowPolicy::DropNewest => return Ok((elements, false)),
+                        }
+                }
+        }
+
+        /// Push as many items as fit without blocking, under a single lock.
+        /// Returns how many were pushed, or the items that did not fit.
+        /// A closed or poisoned channel, or one without receivers, hands every item back
+        pub fn try_send_batch(&self, items: Vec<T>) -> Result<usize, Vec<T>> {
+                let mut elements = self.state.elements.lock();
+                if self.state.is_poisoned() || self.state.is_closed() || self.state.no_receivers.load(Ordering::Relaxed) {
+                        return Err(items);
+                }
+
+                let mut items = items.into_iter();
+                let mut pushed = 0;
+                while self.state.has_room(elements.len()) {
+                        let Some(item) = items.next() else {
+                                break;
+                        };
+                        self.state.observe_send(&item);
+                        elements.push_back(item);
+                        self.state.after_push(elements.len());
+                        pushed += 1;
+                }
+                self.state.notify_receivers(pushed);
+
+                let leftover: Vec<T> = items.collect();
+                if leftover.is_empty() {
+                        Ok(pushed)
+                } else {
+                        Err(leftover)
+                }
+        }
+
         /// Close the channel
         pub fn close(&mut self) -> Result<(), ChannelError> {
-                self.state.closed.store(true, Ordering::Relaxed);
+                self.close_with(CloseReason::Normal);
+                Ok(())
+        }
+
+        /// Close the channel, then block until receivers have drained everything
+        /// already sent. Returns `Timeout` if items are still queued after `timeout`,
+        /// or `NoReceivers` if the last receiver goes away with items left over
+        pub fn close_and_join(&self, timeout: Duration) -> Result<(), ChannelError> {
+                self.close_with(CloseReason::Normal);
+                let deadline = Instant::now() + timeout;
+                let mut elements = self.state.elements.lock();
+                while !elements.is_empty() {
+                        if self.state.no_receivers.load(Ordering::Relaxed) {
+                                return Err(ChannelError::NoReceivers);
+                        }
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                                return Err(ChannelError::Timeout);
+                        }
+                        elements = self.state.drained.wait_timeout(elements, remaining);
+                }
                 Ok(())
         }
+
+        /// Signal that no more items will be sent. Unlike `close` no reason is
+        /// recorded; the receiver drains what is queued and then sees `ChannelClosed`
+        pub fn finish(&self) {
+                self.state.finish();
+        }
+
+        /// Close the channel, recording why it was closed for the receiver.
+        /// Only the first reason is kept if the channel is closed more than once
+        pub fn close_with(&self, reason: CloseReason) {
+                self.state.close(Some(reason));
+        }
 }
 
-impl<T: Clone> Clone for Sender<T> {
+impl<T> Sender<T> {
+        /// Grow the queue's buffer up front so the next `additional` sends don't
+        /// reallocate. Only allocation is affected: the channel is exactly as bounded,
+        /// or unbounded, as before
+        pub fn reserve(&self, additional: usize) {
+                self.state.elements.lock().reserve(additional);
+        }
+}
+
+impl<T, Q> Clone for Sender<T, Q> {
         fn clone(&self) -> Self {
-                // increment the counter
+                // increment the counter under the lock, so `wait_for_senders` can't miss it
+                let _elements = self.state.elements.lock();
                 self.state.num_senders.fetch_add(1, Ordering::Relaxed);
+                self.state.senders_joined.notify_all();
 
                 // return a new sender
                 Sender {
@@ -41,11 +240,18 @@ impl<T: Clone> Clone for Sender<T> {
         }
 }
 
-impl<T> Drop for Sender<T> {
+impl<T, Q> Drop for Sender<T, Q> {
         fn drop(&mut self) {
-                if self.state.num_senders.load(Ordering::Relaxed) == 1 {
-                        self.state.closed.store(true, Ordering::Relaxed);
+                self.state.yield_point(YieldPoint::SenderDrop);
+                // fetch_sub hands back the old count, so exactly one sender sees itself as the last
+                if self.state.num_senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+                        self.state.close(None);
                 }
-                self.state.num_senders.fetch_sub(1, Ordering::Relaxed);
+        }
+}
+
+impl<T, Q: Queue<T>> fmt::Debug for Sender<T, Q> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.state.fmt_debug(f, "Sender")
         }
 }
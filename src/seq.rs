@@ -0,0 +1,122 @@
+use crate::shared_state::{channel_with_queue, ChannelError};
+use crate::queue::Queue;
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::collections::VecDeque;
+
+/// FIFO that tags every item with a sequence number as it is pushed
+pub struct SeqQueue<T> {
+        items: VecDeque<(u64, T)>,
+        next_seq: u64,
+        last_popped: Option<u64>,
+}
+
+impl<T> Default for SeqQueue<T> {
+        fn default() -> Self {
+                SeqQueue { items: VecDeque::new(), next_seq: 0, last_popped: None }
+        }
+}
+
+impl<T> SeqQueue<T> {
+        /// Sequence number of the item popped most recently
+        pub fn last_popped(&self) -> Option<u64> {
+                self.last_popped
+        }
+}
+
+impl<T> Queue<T> for SeqQueue<T> {
+        fn push_back(&mut self, item: T) {
+                // wrapping would take centuries at any realistic send rate, but don't panic if it happens
+                self.items.push_back((self.next_seq, item));
+                self.next_seq = self.next_seq.wrapping_add(1);
+        }
+
+        fn pop_front(&mut self) -> Option<T> {
+                let (seq, item) = self.items.pop_front()?;
+                self.last_popped = Some(seq);
+                Some(item)
+        }
+
+        fn len(&self) -> usize {
+                self.items.len()
+        }
+
+        fn is_empty(&self) -> bool {
+                self.items.is_empty()
+        }
+}
+
+/// Sender part of a sequenced channel
+pub type SeqSender<T> = Sender<T, SeqQueue<T>>;
+
+/// Receiver part of a sequenced channel
+pub type SeqReceiver<T> = Receiver<T, SeqQueue<T>>;
+
+/// Create a channel that numbers items in the order they are sent, starting at
+/// zero. The number is assigned under the queue lock, so it matches queue order
+/// across every sender, and a gap means an item was dropped on the way
+pub fn sequenced_channel<T>() -> (SeqSender<T>, SeqReceiver<T>) {
+        channel_with_queue(SeqQueue::default())
+}
+
+impl<T> Receiver<T, SeqQueue<T>> {
+        /// Like `recv`, but also returns the item's sequence number
+        pub fn recv_seq(&self) -> Result<(u64, T), ChannelError> {
+                let mut elements = self.wait_for_items()?;
+                let item = self.pop(&mut elements).unwrap();
+                Ok((elements.last_popped().unwrap(), item))
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use std::thread;
+
+        #[test]
+        fn test_sequence_numbers() {
+                let (tx, rx) = sequenced_channel();
+                tx.send("a").unwrap();
+                tx.send("b").unwrap();
+                tx.send("c").unwrap();
+                assert_eq!(rx.recv_seq().unwrap(), (0, "a"));
+                // plain recv still works and uses up a number
+                assert_eq!(rx.recv().unwrap(), "b");
+                assert_eq!(rx.recv_seq().unwrap(), (2, "c"));
+        }
+
+        #[test]
+        fn test_sequence_matches_queue_order_across_senders() {
+                let (tx, rx) = sequenced_channel();
+                let senders: Vec<_> = (0..4).map(|_| {
+                        let tx = tx.clone();
+                        thread::spawn(move || {
+                                for i in 0..100 {
+                                        tx.send(i).unwrap();
+                                }
+                        })
+                }).collect();
+                drop(tx);
+                for sender in senders {
+                        sender.join().unwrap();
+                }
+
+                let mut expected = 0;
+                while let Ok((seq, _)) = rx.recv_seq() {
+                        assert_eq!(seq, expected);
+                        expected += 1;
+                }
+                assert_eq!(expected, 400);
+        }
+
+        #[test]
+        fn test_wraparound() {
+                let mut queue = SeqQueue { items: VecDeque::new(), next_seq: u64::MAX, last_popped: None };
+                queue.push_back(1);
+                queue.push_back(2);
+                queue.pop_front();
+                assert_eq!(queue.last_popped(), Some(u64::MAX));
+                queue.pop_front();
+                assert_eq!(queue.last_popped(), Some(0));
+        }
+}
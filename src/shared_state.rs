@@ -1,60 +1,617 @@
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex, Condvar};
-use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize};
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
 use crate::sender::Sender;
 use crate::receiver::Receiver;
+use crate::queue::Queue;
+use crate::sync::{Mutex, Condvar};
 
 #[derive(Debug)]
 pub enum ChannelError {
         ChannelClosed,
         ChannelEmpty,
-        RecvBlocked
+        RecvBlocked,
+        /// A cancellable receive was told to stop
+        Cancelled,
+        /// Nothing arrived before the timeout
+        Timeout,
+        /// Every receiver has been dropped, so nothing would ever read the item
+        NoReceivers,
+        /// A bounded channel had no room and the send was not allowed to wait
+        Full,
+        /// Some thread panicked while holding the queue lock, e.g. in a send
+        /// observer or in a `recv_matching` predicate. Reported to receivers once
+        /// everything queued before that has been received, and to every send
+        Poisoned,
 }
 
+/// Snapshot of a channel's state, see `Receiver::status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelStatus {
+        /// Still open, more items may arrive
+        Open,
+        /// Closed, with this many items left to drain
+        ClosedNonEmpty(usize),
+        /// Closed and fully drained
+        ClosedEmpty,
+}
+
+/// A failed send, handing the item back to the caller
+pub struct SendError<T> {
+        pub item: T,
+        pub error: ChannelError,
+}
+
+impl<T> SendError<T> {
+        /// Take back the item that could not be sent
+        pub fn into_inner(self) -> T {
+                self.item
+        }
+}
+
+impl<T> From<SendError<T>> for ChannelError {
+        fn from(err: SendError<T>) -> Self {
+                err.error
+        }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("SendError").field("error", &self.error).finish_non_exhaustive()
+        }
+}
+
+/// Why a channel was closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+        /// The producer finished normally
+        Normal,
+        /// The producer aborted with an error
+        Error(String),
+}
+
+
+/// What `send` does when a bounded channel is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+        /// Wait for the receiver to make room
+        #[default]
+        Block,
+        /// Throw away the oldest queued item to make room
+        DropOldest,
+        /// Throw away the item being sent
+        DropNewest,
+}
+
+/// How senders wake receivers parked on an empty channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyStrategy {
+        /// Wake a single receiver, enough for one item and one consumer
+        One,
+        /// Wake every receiver
+        All,
+}
+
+/// Spots in the send, receive and drop paths where tests can force an interleaving
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum YieldPoint {
+        /// A sender holds the lock, right before it checks whether the channel is closed
+        Send,
+        /// The receiver holds the lock and found the queue empty, right before it waits
+        RecvWait,
+        /// A sender is being dropped, right before it gives up its count
+        SenderDrop,
+}
+
+/// Callback invoked with each item as it is sent
+pub type SendObserver<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+/// Callback invoked with each item an overflow policy throws away
+pub type DropHook<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+/// Callback invoked with the queue length when it crosses the high watermark
+pub type WatermarkHook = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Soft limits on an unbounded channel's backlog, see `channel_with_watermark`
+pub struct Watermark {
+        pub high: usize,
+        pub low: usize,
+        pub on_high: WatermarkHook,
+}
+
+/// Wakes receivers when a hook unwinds, see `SharedState::run_hook`
+struct HookGuard<'a, T, Q>(&'a SharedState<T, Q>);
+
+impl<T, Q> Drop for HookGuard<'_, T, Q> {
+        fn drop(&mut self) {
+                if std::thread::panicking() {
+                        self.0.is_empty.notify_all();
+                        self.0.wake_streams();
+                }
+        }
+}
+
+/// Tells a deadline timer thread to stop early, once the channel it would close
+/// is gone, see `channel_with_deadline`
+#[derive(Default)]
+pub struct DeadlineTimer {
+        stopped: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl DeadlineTimer {
+        /// Sleep until `deadline`, then close the channel if it is still around
+        fn spawn<T: Send + 'static>(&self, state: Weak<SharedState<T>>, deadline: Instant) {
+                let stopped = self.stopped.clone();
+                thread::spawn(move || {
+                        let (flag, condvar) = &*stopped;
+                        let mut flag = flag.lock();
+                        while !*flag {
+                                let remaining = deadline.saturating_duration_since(Instant::now());
+                                if remaining.is_zero() {
+                                        drop(flag);
+                                        if let Some(state) = state.upgrade() {
+                                                state.close(None);
+                                        }
+                                        return;
+                                }
+                                flag = condvar.wait_timeout(flag, remaining);
+                        }
+                });
+        }
+}
+
+impl Drop for DeadlineTimer {
+        fn drop(&mut self) {
+                let (flag, condvar) = &*self.stopped;
+                *flag.lock() = true;
+                condvar.notify_all();
+        }
+}
 
 /// The shared state between the sender and the receiver
-pub struct SharedState<T> {
-        pub elements: Mutex<VecDeque<T>>,
+pub struct SharedState<T, Q = VecDeque<T>> {
+        pub elements: Mutex<Q>,
         pub is_empty: Condvar,
+        pub is_full: Condvar,
+        pub senders_joined: Condvar,
+        pub drained: Condvar,
         pub closed: AtomicBool,
+        pub finishing: AtomicBool,
         pub num_senders: AtomicUsize,
+        pub num_receivers: AtomicUsize,
+        pub waiting_receivers: AtomicUsize,
+        pub max_len: AtomicUsize,
+        pub next_receiver_id: AtomicUsize,
+        pub round_robin: bool,
+        pub last_served: AtomicUsize,
+        pub no_receivers: AtomicBool,
+        pub close_reason: Mutex<Option<CloseReason>>,
+        pub on_send: Option<SendObserver<T>>,
+        pub deadline: Option<Instant>,
+        pub deadline_timer: Option<DeadlineTimer>,
+        pub capacity: Option<usize>,
+        pub overflow: OverflowPolicy,
+        pub spins: u32,
+        pub name: Option<&'static str>,
+        pub on_drop: Option<DropHook<T>>,
+        pub recycled: Mutex<Vec<T>>,
+        pub watermark: Option<Watermark>,
+        pub watermark_active: AtomicBool,
+        #[cfg(feature = "futures")]
+        pub wakers: Mutex<Vec<std::task::Waker>>,
+        #[cfg(test)]
+        pub(crate) yield_hook: Option<Arc<dyn Fn(YieldPoint) + Send + Sync>>,
+}
+
+impl<T, Q> SharedState<T, Q> {
+        /// Fresh state for an open channel over `queue` with a single sender
+        pub(crate) fn new(queue: Q) -> Self {
+                SharedState {
+                        elements: Mutex::new(queue),
+                        is_empty: Condvar::new(),
+                        is_full: Condvar::new(),
+                        senders_joined: Condvar::new(),
+                        drained: Condvar::new(),
+                        closed: AtomicBool::new(false),
+                        finishing: AtomicBool::new(false),
+                        num_senders: AtomicUsize::new(1),
+                        num_receivers: AtomicUsize::new(1),
+                        waiting_receivers: AtomicUsize::new(0),
+                        max_len: AtomicUsize::new(0),
+                        next_receiver_id: AtomicUsize::new(1),
+                        round_robin: false,
+                        last_served: AtomicUsize::new(usize::MAX),
+                        no_receivers: AtomicBool::new(false),
+                        close_reason: Mutex::new(None),
+                        on_send: None,
+                        deadline: None,
+                        deadline_timer: None,
+                        capacity: None,
+                        overflow: OverflowPolicy::Block,
+                        spins: 0,
+                        name: None,
+                        on_drop: None,
+                        recycled: Mutex::new(Vec::new()),
+                        watermark: None,
+                        watermark_active: AtomicBool::new(false),
+                        #[cfg(feature = "futures")]
+                        wakers: Mutex::new(Vec::new()),
+                        #[cfg(test)]
+                        yield_hook: None,
+                }
+        }
+
+        /// Report an item thrown away by the overflow policy, right before it is freed
+        pub(crate) fn dropped(&self, item: &T) {
+                if let Some(on_drop) = &self.on_drop {
+                        self.run_hook(|| on_drop(item));
+                }
+        }
+
+        /// Show an item to the send observer, right before it is pushed
+        pub(crate) fn observe_send(&self, item: &T) {
+                if let Some(on_send) = &self.on_send {
+                        self.run_hook(|| on_send(item));
+                }
+        }
+
+        /// Run a user hook or closure with the queue lock held. If it panics the lock is
+        /// about to be poisoned, so parked receivers are woken to notice
+        pub(crate) fn run_hook<R>(&self, hook: impl FnOnce() -> R) -> R {
+                let _guard = HookGuard(self);
+                hook()
+        }
+
+        /// Let a test hook run at `point`. Compiles to nothing outside of tests
+        #[inline(always)]
+        pub(crate) fn yield_point(&self, point: YieldPoint) {
+                #[cfg(test)]
+                if let Some(hook) = &self.yield_hook {
+                        hook(point);
+                }
+                #[cfg(not(test))]
+                let _ = point;
+        }
+
+        /// How to wake receivers after `pushed` items were queued. A single item for a
+        /// single consumer only needs one wakeup. Batches can feed several receivers, and
+        /// with several receivers the one woken may be a `recv_matching` that doesn't
+        /// want the item, so both wake everyone
+        pub fn notify_strategy(&self, pushed: usize) -> NotifyStrategy {
+                if pushed > 1 || self.num_receivers.load(Ordering::Relaxed) > 1 {
+                        NotifyStrategy::All
+                } else {
+                        NotifyStrategy::One
+                }
+        }
+
+        /// Wake receivers after `pushed` items were queued. Called with the queue lock held
+        pub(crate) fn notify_receivers(&self, pushed: usize) {
+                if pushed == 0 {
+                        return;
+                }
+                match self.notify_strategy(pushed) {
+                        NotifyStrategy::One => self.is_empty.notify_one(),
+                        NotifyStrategy::All => self.is_empty.notify_all(),
+                }
+                self.wake_streams();
+        }
+
+        /// Wake any streams waiting on the channel. The wakers are taken out of the list
+        /// first, so one that polls its stream right away doesn't find the list locked
+        #[inline(always)]
+        pub(crate) fn wake_streams(&self) {
+                #[cfg(feature = "futures")]
+                for waker in std::mem::take(&mut *self.wakers.lock()) {
+                        waker.wake();
+                }
+        }
+
+        /// Mark the channel closed and wake everyone waiting on it. The reason
+        /// is only recorded if the channel was still open
+        pub(crate) fn close(&self, reason: Option<CloseReason>) {
+                let elements = self.elements.lock();
+                let mut close_reason = self.close_reason.lock();
+                if !self.closed.load(Ordering::Relaxed) {
+                        *close_reason = reason;
+                        self.closed.store(true, Ordering::Relaxed);
+                }
+                self.is_empty.notify_all();
+                self.is_full.notify_all();
+                self.senders_joined.notify_all();
+                drop(close_reason);
+                // a stream polled from now on sees the channel closed, so it is safe to
+                // wake outside the lock, where a waker can poll the stream inline
+                drop(elements);
+                self.wake_streams();
+        }
+
+        /// Let `close_and_join` know the queue is empty, after receiving left `len`
+        /// items. Called with the queue lock held
+        pub(crate) fn notify_drained(&self, len: usize) {
+                if len == 0 {
+                        self.drained.notify_all();
+                }
+        }
+
+        /// Mark that no more items will be sent, without closing the channel. Receivers
+        /// keep draining and only see `ChannelClosed` once the queue is empty
+        pub(crate) fn finish(&self) {
+                let elements = self.elements.lock();
+                self.finishing.store(true, Ordering::Relaxed);
+                self.is_empty.notify_all();
+                self.is_full.notify_all();
+                self.senders_joined.notify_all();
+                drop(elements);
+                self.wake_streams();
+        }
+
+        /// Bookkeeping after a push left `len` items queued. Called with the queue lock held
+        pub(crate) fn after_push(&self, len: usize) {
+                self.max_len.fetch_max(len, Ordering::Relaxed);
+                self.check_watermark(len);
+        }
+
+        /// Fire the high watermark hook if a push took the queue to `len` items above
+        /// it. The hook fires once per crossing and is only armed again after a
+        /// push finds the queue back down at the low watermark. The length before
+        /// the push is what's compared against `low`, so `low == 0` still re-arms
+        /// once the queue drains. Called with the queue lock held
+        fn check_watermark(&self, len: usize) {
+                let Some(watermark) = &self.watermark else {
+                        return;
+                };
+                let mut active = self.watermark_active.load(Ordering::Relaxed);
+                if active && len.saturating_sub(1) <= watermark.low {
+                        self.watermark_active.store(false, Ordering::Relaxed);
+                        active = false;
+                }
+                if !active && len > watermark.high {
+                        self.watermark_active.store(true, Ordering::Relaxed);
+                        self.run_hook(|| (watermark.on_high)(len));
+                }
+        }
+
+        /// Whether some thread panicked while holding the queue lock. The queue is
+        /// still usable, but whatever that thread was in the middle of is lost
+        pub(crate) fn is_poisoned(&self) -> bool {
+                self.elements.is_poisoned()
+        }
+
+        /// Why a receiver that found the queue empty should stop waiting: `Poisoned` if
+        /// a sender panicked holding the lock, `ChannelClosed` if the channel is
+        /// closed, or `None` while more items may still arrive
+        pub(crate) fn empty_error(&self) -> Option<ChannelError> {
+                if self.is_poisoned() {
+                        Some(ChannelError::Poisoned)
+                } else if self.is_closed() {
+                        Some(ChannelError::ChannelClosed)
+                } else {
+                        None
+                }
+        }
+
+        /// Whether a queue holding `len` items can take another one
+        pub(crate) fn has_room(&self, len: usize) -> bool {
+                self.capacity.is_none_or(|capacity| len < capacity)
+        }
+
+        /// Whether the channel is closed, closing it first if its deadline has passed.
+        /// A finished channel counts as closed, so sends fail and receivers stop
+        /// waiting once the queue runs dry
+        pub(crate) fn is_closed(&self) -> bool {
+                if let Some(deadline) = self.deadline {
+                        if Instant::now() >= deadline && !self.closed.swap(true, Ordering::Relaxed) {
+                                // the timer thread may not have got to it yet
+                                self.wake_streams();
+                        }
+                }
+                self.closed.load(Ordering::Relaxed) || self.finishing.load(Ordering::Relaxed)
+        }
+
+        /// Shared `Debug` output for `Sender` and `Receiver`
+        pub(crate) fn fmt_debug(&self, f: &mut fmt::Formatter<'_>, type_name: &str) -> fmt::Result
+        where
+                Q: Queue<T>,
+        {
+                let mut debug = f.debug_struct(type_name);
+                if let Some(name) = self.name {
+                        debug.field("name", &name);
+                }
+                debug.field("queued", &self.elements.lock().len())
+                        .field("closed", &self.is_closed())
+                        .field("senders", &self.num_senders.load(Ordering::Relaxed))
+                        .finish()
+        }
+
+        /// Wrap the state up into a sender and receiver pair
+        pub(crate) fn into_channel(self) -> (Sender<T, Q>, Receiver<T, Q>) {
+                let shared_state = Arc::new(self);
+                let sender = Sender { state: shared_state.clone() };
+                let receiver = Receiver { state: shared_state, id: 0 };
+                (sender, receiver)
+        }
 }
 
 
+/// Sender part of an unbounded channel
+pub type UnboundedSender<T> = Sender<T>;
+
+/// Receiver part of an unbounded channel
+pub type UnboundedReceiver<T> = Receiver<T>;
+
+/// Create a new channel with no capacity limit, where `send` never blocks.
+///
+/// `T` is not required to be `'static`, so the channel can carry borrowed data
+/// (e.g. `&'scope [u8]`) between threads spawned with `std::thread::scope`.
+/// The lifetime is part of `T` itself, so no separate lifetime parameter is needed
+pub fn unbounded<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+        channel_with_queue(VecDeque::new())
+}
+
+/// Create a new unbounded channel. The name doesn't say whether the channel is
+/// bounded, so spell it out with `unbounded` or `bounded` instead
+#[deprecated(note = "use `unbounded` or `bounded` to make the capacity explicit")]
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
-        let shared_state = Arc::new(SharedState {
-                elements: Mutex::new(VecDeque::new()),
-                is_empty: Condvar::new(),
-                closed: AtomicBool::new(false),
-                num_senders: AtomicUsize::new(1)
-        });
-
-        let sender = Sender { state: shared_state.clone() };
-        let receiver = Receiver { state: shared_state };
-        (sender, receiver)
+        unbounded()
+}
+
+/// Create a new channel over a user provided queue. If the queue reports a
+/// capacity, `send` blocks while it is full
+pub fn channel_with_queue<T, Q: Queue<T>>(queue: Q) -> (Sender<T, Q>, Receiver<T, Q>) {
+        let mut state = SharedState::new(queue);
+        state.capacity = state.elements.get_mut().capacity();
+        state.into_channel()
+}
+
+/// Create a new unbounded channel whose queue starts with room for `initial`
+/// items, so a known burst doesn't reallocate. Like `Sender::reserve`, this
+/// affects allocation only and never makes `send` block
+pub fn channel_with_capacity<T>(initial: usize) -> (Sender<T>, Receiver<T>) {
+        channel_with_queue(VecDeque::with_capacity(initial))
+}
+
+/// Create a new channel holding at most `capacity` items, where `send`
+/// blocks while the channel is full
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+        bounded_with_policy(capacity, OverflowPolicy::Block)
+}
+
+/// Create a new bounded channel that handles a full queue according to `overflow`
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero
+pub fn bounded_with_policy<T>(capacity: usize, overflow: OverflowPolicy) -> (Sender<T>, Receiver<T>) {
+        assert!(capacity > 0, "bounded channel capacity must be non-zero");
+        let mut state = SharedState::new(VecDeque::new());
+        state.capacity = Some(capacity);
+        state.overflow = overflow;
+        state.into_channel()
+}
+
+/// Like `bounded_with_policy`, but calls `on_drop` with every item the policy
+/// throws away. The hook runs with the queue lock held
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero
+pub fn bounded_with_drop_hook<T>(
+        capacity: usize,
+        overflow: OverflowPolicy,
+        on_drop: impl Fn(&T) + Send + Sync + 'static,
+) -> (Sender<T>, Receiver<T>) {
+        assert!(capacity > 0, "bounded channel capacity must be non-zero");
+        let mut state = SharedState::new(VecDeque::new());
+        state.capacity = Some(capacity);
+        state.overflow = overflow;
+        state.on_drop = Some(Arc::new(on_drop));
+        state.into_channel()
+}
+
+/// Create a new channel that calls `on_send` with every item that is sent.
+///
+/// The observer runs while the queue lock is held, right before the item is
+/// pushed, so it sees items in queue order and is never called for a send that
+/// fails. Keep it cheap, since it holds up every other sender and the receiver
+pub fn channel_with_observer<T>(on_send: impl Fn(&T) + Send + Sync + 'static) -> (Sender<T>, Receiver<T>) {
+        let mut state = SharedState::new(VecDeque::new());
+        state.on_send = Some(Arc::new(on_send));
+        state.into_channel()
 }
     
+/// Create a new channel that closes itself once `ttl` has passed since creation.
+///
+/// A timer thread closes the channel at the deadline, waking blocked receivers
+/// and pending streams, and exits early if the channel is dropped before then.
+/// Items sent before the deadline can still be drained afterwards
+pub fn channel_with_deadline<T: Send + 'static>(ttl: Duration) -> (Sender<T>, Receiver<T>) {
+        let deadline = Instant::now() + ttl;
+        let mut state = SharedState::new(VecDeque::new());
+        state.deadline = Some(deadline);
+        state.deadline_timer = Some(DeadlineTimer::default());
+        let (sender, receiver) = state.into_channel();
+        if let Some(timer) = &sender.state.deadline_timer {
+                timer.spawn(Arc::downgrade(&sender.state), deadline);
+        }
+        (sender, receiver)
+}
+
+/// Create a new channel whose receiver spins up to `spins` rounds, with
+/// exponential backoff, before parking on an empty queue. Trades CPU for lower
+/// wakeup latency under bursty producers. On a single core machine spinning only
+/// steals time from the producer, so there it is turned off
+pub fn channel_with_spin<T>(spins: u32) -> (Sender<T>, Receiver<T>) {
+        let mut state = SharedState::new(VecDeque::new());
+        let multi_core = std::thread::available_parallelism().is_ok_and(|cores| cores.get() > 1);
+        state.spins = if multi_core { spins } else { 0 };
+        state.into_channel()
+}
+
+/// Create a new unbounded channel that warns about a growing backlog without
+/// ever blocking `send`. Once a send takes the queue above `high` items,
+/// `on_high_watermark` is called with the queue length. It isn't called again
+/// until a later send is made with `low` items or fewer queued, so a
+/// backlog hovering around `high` doesn't fire it over and over. The hook runs
+/// with the queue lock held
+pub fn channel_with_watermark<T>(
+        high: usize,
+        low: usize,
+        on_high_watermark: impl Fn(usize) + Send + Sync + 'static,
+) -> (Sender<T>, Receiver<T>) {
+        assert!(low <= high, "low watermark must not be above the high watermark");
+        let mut state = SharedState::new(VecDeque::new());
+        state.watermark = Some(Watermark { high, low, on_high: Arc::new(on_high_watermark) });
+        state.into_channel()
+}
+
+/// Create a new channel for a pool of cloned receivers that spreads items across
+/// them. A receiver that took the previous item and wakes up to find another
+/// one waiting hands it to an idle receiver first, if there is one, and only
+/// takes it itself when nobody else picks it up within a few microseconds.
+/// This is a hint, not strict fairness
+pub fn work_stealing_channel<T>() -> (Sender<T>, Receiver<T>) {
+        let mut state = SharedState::new(VecDeque::new());
+        state.round_robin = true;
+        state.into_channel()
+}
+
+/// Create a new channel with a name, shown in its `Debug` output to tell it
+/// apart from the other channels in an application
+pub fn channel_named<T>(name: &'static str) -> (Sender<T>, Receiver<T>) {
+        let mut state = SharedState::new(VecDeque::new());
+        state.name = Some(name);
+        state.into_channel()
+}
+
 #[cfg(test)]
 mod tests {
         use super::*;
+        use std::thread;
 
         #[test]
         fn test_channel_creation() {
-            let (tx, rx) = channel::<i32>();
+            let (_tx, _rx) = unbounded::<i32>();
             // Basic test to ensure channel creation doesn't panic
         }
 
         #[test]
         fn test_send_and_recv() {
-                let (tx, rx) = channel::<usize>();
+                let (tx, rx) = unbounded::<usize>();
                 tx.send(10).unwrap();
                 assert_eq!(rx.recv().unwrap(), 10);
         }
 
         #[test]
         fn test_multiple_send_and_recv() {
-                let (tx, rx) = channel::<usize>();
+                let (tx, rx) = unbounded::<usize>();
                 tx.send(10).unwrap();
                 tx.send(11).unwrap();
                 tx.send(12).unwrap();
@@ -67,7 +624,7 @@ mod tests {
 
         #[test]
         fn test_send_and_try_recv() {
-            let (tx, rx) = channel();
+            let (tx, rx) = unbounded();
             tx.send(42).unwrap();
             assert_eq!(rx.try_recv().unwrap(), 42);
             assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelEmpty)));
@@ -75,15 +632,15 @@ mod tests {
     
         #[test]
         fn test_send_after_close() {
-            let (mut tx, rx) = channel::<i32>();
+            let (mut tx, rx) = unbounded::<i32>();
             tx.close().unwrap();
-            assert!(matches!(tx.send(42), Err(ChannelError::ChannelClosed)));
+            assert!(matches!(tx.send(42), Err(SendError { error: ChannelError::ChannelClosed, .. })));
             assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
         }
     
         #[test]
         fn test_send_and_recv_multiple_threads() {
-            let (tx, rx) = channel();
+            let (tx, rx) = unbounded();
             let tx_thread = thread::spawn(move || {
                 for i in 0..100 {
                     tx.send(i).unwrap();
@@ -100,4 +657,1067 @@ mod tests {
             assert_eq!(rx_thread.join().unwrap(), 4950); // sum of 0..99
         }
 
-}
\ No newline at end of file
+        #[test]
+        fn test_close_with_reason() {
+                let (tx, rx) = unbounded::<i32>();
+                tx.send(1).unwrap();
+                assert_eq!(rx.close_reason(), None);
+                tx.close_with(CloseReason::Error("disk full".to_string()));
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+                assert_eq!(rx.close_reason(), Some(CloseReason::Error("disk full".to_string())));
+        }
+
+        #[test]
+        fn test_close_wakes_blocked_receiver() {
+                let (tx, rx) = unbounded::<i32>();
+                let rx_thread = thread::spawn(move || rx.recv().is_err() && rx.close_reason() == Some(CloseReason::Normal));
+                thread::sleep(std::time::Duration::from_millis(50));
+                tx.close_with(CloseReason::Normal);
+                assert!(rx_thread.join().unwrap());
+        }
+
+        #[test]
+        fn test_debug_without_t_debug() {
+                struct NotDebug;
+                let (tx, rx) = unbounded::<NotDebug>();
+                tx.send(NotDebug).unwrap();
+                assert_eq!(format!("{:?}", tx), "Sender { queued: 1, closed: false, senders: 1 }");
+                assert_eq!(format!("{:?}", rx), "Receiver { queued: 1, closed: false, senders: 1 }");
+
+                // a finished channel reads as closed, like `status` says
+                tx.finish();
+                assert_eq!(format!("{:?}", rx), "Receiver { queued: 1, closed: true, senders: 1 }");
+        }
+
+        #[test]
+        fn test_poll_recv() {
+                let (mut tx, rx) = unbounded::<i32>();
+                let timeout = std::time::Duration::from_millis(10);
+                assert!(matches!(rx.poll_recv(timeout), Err(ChannelError::ChannelEmpty)));
+                tx.send(7).unwrap();
+                assert_eq!(rx.poll_recv(timeout).unwrap(), 7);
+
+                // holding the lock makes the poll give up once the timeout passes
+                let guard = rx.state.elements.lock();
+                assert!(matches!(rx.poll_recv(timeout), Err(ChannelError::RecvBlocked)));
+                drop(guard);
+
+                tx.close().unwrap();
+                assert!(matches!(rx.poll_recv(timeout), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_scoped_borrowed_slices() {
+                let data = [1, 2, 3, 4, 5, 6];
+                let (tx, rx) = unbounded::<&[i32]>();
+                thread::scope(|s| {
+                        s.spawn(|| {
+                                for chunk in data.chunks(2) {
+                                        tx.send(chunk).unwrap();
+                                }
+                        });
+                        let sums: Vec<i32> = (0..3).map(|_| rx.recv().unwrap().iter().sum()).collect();
+                        assert_eq!(sums, vec![3, 7, 11]);
+                });
+        }
+
+        const fn assert_send<T: Send>() {}
+        const fn assert_sync<T: Sync>() {}
+
+        #[test]
+        fn test_send_sync_bounds() {
+                const _: () = assert_send::<Sender<i32>>();
+                const _: () = assert_sync::<Sender<i32>>();
+                const _: () = assert_send::<Receiver<i32>>();
+                const _: () = assert_sync::<Receiver<i32>>();
+
+                // only `T: Send` is required, not `T: Sync`
+                const _: () = assert_send::<Sender<std::cell::Cell<i32>>>();
+                const _: () = assert_send::<Receiver<std::cell::Cell<i32>>>();
+        }
+
+        #[test]
+        fn test_observer_counts_sends() {
+                let count = Arc::new(AtomicUsize::new(0));
+                let observed = count.clone();
+                let (mut tx, rx) = channel_with_observer(move |_: &i32| {
+                        observed.fetch_add(1, Ordering::Relaxed);
+                });
+                for i in 0..5 {
+                        tx.send(i).unwrap();
+                }
+                tx.close().unwrap();
+                assert!(tx.send(5).is_err());
+                assert_eq!(count.load(Ordering::Relaxed), 5);
+                assert_eq!(rx.recv().unwrap(), 0);
+        }
+
+        #[test]
+        fn test_deadline_closes_channel() {
+                let (tx, rx) = channel_with_deadline::<i32>(Duration::from_millis(50));
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+                thread::sleep(Duration::from_millis(80));
+                assert!(matches!(tx.send(3), Err(SendError { error: ChannelError::ChannelClosed, .. })));
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert_eq!(rx.recv().unwrap(), 2);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_deadline_wakes_blocked_receiver() {
+                let (_tx, rx) = channel_with_deadline::<i32>(Duration::from_millis(50));
+                let start = Instant::now();
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+                assert!(start.elapsed() >= Duration::from_millis(50));
+        }
+
+        #[test]
+        fn test_recv_with_depth() {
+                let (tx, rx) = unbounded();
+                for i in 0..3 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.recv_with_depth().unwrap(), (0, 2));
+                assert_eq!(rx.recv_with_depth().unwrap(), (1, 1));
+                assert_eq!(rx.recv_with_depth().unwrap(), (2, 0));
+        }
+
+        #[test]
+        fn test_bounded_send_blocks_when_full() {
+                let (tx, rx) = bounded(2);
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..100 {
+                                tx.send(i).unwrap();
+                        }
+                });
+                for i in 0..100 {
+                        assert_eq!(rx.recv().unwrap(), i);
+                        assert!(rx.state.elements.lock().len() <= 2);
+                }
+                tx_thread.join().unwrap();
+        }
+
+        #[test]
+        fn test_try_send_batch() {
+                let (mut tx, rx) = bounded(3);
+                tx.send(0).unwrap();
+                assert_eq!(tx.try_send_batch(vec![1, 2, 3, 4]), Err(vec![3, 4]));
+                assert_eq!(rx.recv().unwrap(), 0);
+                assert_eq!(tx.try_send_batch(vec![3]), Ok(1));
+                tx.close().unwrap();
+                assert_eq!(tx.try_send_batch(vec![5, 6]), Err(vec![5, 6]));
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert_eq!(rx.recv().unwrap(), 2);
+                assert_eq!(rx.recv().unwrap(), 3);
+        }
+
+        #[test]
+        fn test_try_send_batch_unbounded() {
+                let (tx, rx) = unbounded();
+                assert_eq!(tx.try_send_batch((0..10).collect()), Ok(10));
+                assert_eq!(rx.recv_with_depth().unwrap(), (0, 9));
+        }
+
+        #[test]
+        fn test_channel_with_custom_queue() {
+                // a queue that hands items out newest first
+                struct Stack(Vec<i32>);
+
+                impl Queue<i32> for Stack {
+                        fn push_back(&mut self, item: i32) {
+                                self.0.push(item);
+                        }
+
+                        fn pop_front(&mut self) -> Option<i32> {
+                                self.0.pop()
+                        }
+
+                        fn len(&self) -> usize {
+                                self.0.len()
+                        }
+
+                        fn is_empty(&self) -> bool {
+                                self.0.is_empty()
+                        }
+                }
+
+                let (tx, rx) = channel_with_queue(Stack(Vec::new()));
+                for i in 0..3 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.recv().unwrap(), 2);
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert_eq!(rx.recv().unwrap(), 0);
+        }
+
+        // channel whose state runs `hook` at every yield point
+        fn channel_with_hook<T>(hook: impl Fn(YieldPoint) + Send + Sync + 'static) -> (Sender<T>, Receiver<T>) {
+                let mut state = SharedState::new(VecDeque::new());
+                state.yield_hook = Some(Arc::new(hook));
+                state.into_channel()
+        }
+
+        #[test]
+        fn test_last_sender_drop_wakes_parked_receiver() {
+                let (parked_tx, parked_rx) = std::sync::mpsc::channel();
+                let (tx, rx) = channel_with_hook::<i32>(move |point| {
+                        if point == YieldPoint::RecvWait {
+                                let _ = parked_tx.send(());
+                        }
+                });
+                let rx_thread = thread::spawn(move || rx.recv());
+
+                // only drop once the receiver is about to park on the condvar
+                parked_rx.recv().unwrap();
+                drop(tx);
+                assert!(matches!(rx_thread.join().unwrap(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_concurrent_sender_drops_close_channel() {
+                // both senders reach the drop path before either gives up its count
+                let barrier = Arc::new(std::sync::Barrier::new(2));
+                let (tx, rx) = channel_with_hook::<i32>(move |point| {
+                        if point == YieldPoint::SenderDrop {
+                                barrier.wait();
+                        }
+                });
+                let tx2 = tx.clone();
+                let a = thread::spawn(move || drop(tx));
+                let b = thread::spawn(move || drop(tx2));
+                a.join().unwrap();
+                b.join().unwrap();
+                assert_eq!(rx.state.num_senders.load(Ordering::Relaxed), 0);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_drop_during_send_leaves_channel_open() {
+                // the second sender is dropped while the first holds the lock mid send
+                let (dropped_tx, dropped_rx) = std::sync::mpsc::channel::<()>();
+                let dropped_rx = Mutex::new(dropped_rx);
+                let (tx, rx) = channel_with_hook::<i32>(move |point| {
+                        if point == YieldPoint::Send {
+                                let _ = dropped_rx.lock().recv();
+                        }
+                });
+                let tx2 = tx.clone();
+                let send_thread = thread::spawn(move || tx.send(1));
+                drop(tx2);
+                dropped_tx.send(()).unwrap();
+                send_thread.join().unwrap().unwrap();
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_recv_interruptible() {
+                let (tx, rx) = unbounded::<i32>();
+                let cancel = Arc::new(AtomicBool::new(false));
+                tx.send(1).unwrap();
+                assert_eq!(rx.recv_interruptible(&cancel).unwrap(), 1);
+
+                let flag = cancel.clone();
+                let worker = rx.clone();
+                let rx_thread = thread::spawn(move || worker.recv_interruptible(&flag));
+                thread::sleep(Duration::from_millis(30));
+                cancel.store(true, Ordering::Relaxed);
+                assert!(matches!(rx_thread.join().unwrap(), Err(ChannelError::Cancelled)));
+
+                // the channel itself is still open for other consumers
+                tx.send(2).unwrap();
+                assert_eq!(rx.recv().unwrap(), 2);
+        }
+
+        #[test]
+        fn test_into_inner() {
+                let (tx, rx) = unbounded();
+                for i in 0..3 {
+                        tx.send(i).unwrap();
+                }
+                drop(tx);
+                assert_eq!(rx.into_inner(), vec![0, 1, 2]);
+        }
+
+        #[test]
+        fn test_into_inner_with_live_sender() {
+                let (tx, rx) = unbounded();
+                tx.send(1).unwrap();
+                assert_eq!(rx.into_inner(), vec![1]);
+                // the receiver is gone, so the sender finds out on its next send
+                assert!(matches!(tx.send(2), Err(SendError { error: ChannelError::NoReceivers, .. })));
+        }
+
+        #[test]
+        fn test_recv_matching() {
+                let (tx, rx) = unbounded();
+                for i in [1, 3, 4, 5, 6] {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.recv_matching(|x| x % 2 == 0).unwrap(), 4);
+                assert_eq!(rx.recv_matching(|x| x % 2 == 0).unwrap(), 6);
+                assert_eq!(rx.recv().unwrap(), 1);
+
+                // blocks until a matching item shows up
+                let tx_thread = thread::spawn(move || {
+                        thread::sleep(Duration::from_millis(20));
+                        tx.send(7).unwrap();
+                        tx.send(8).unwrap();
+                });
+                assert_eq!(rx.recv_matching(|x| *x == 8).unwrap(), 8);
+                tx_thread.join().unwrap();
+                assert_eq!(rx.recv().unwrap(), 3);
+                assert_eq!(rx.recv().unwrap(), 5);
+                assert_eq!(rx.recv().unwrap(), 7);
+        }
+
+        #[test]
+        fn test_drop_oldest_hook() {
+                let dropped = Arc::new(Mutex::new(Vec::new()));
+                let seen = dropped.clone();
+                let (tx, rx) = bounded_with_drop_hook(2, OverflowPolicy::DropOldest, move |item: &i32| {
+                        seen.lock().push(*item);
+                });
+                for i in 0..5 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(*dropped.lock(), vec![0, 1, 2]);
+                assert_eq!(rx.recv().unwrap(), 3);
+                assert_eq!(rx.recv().unwrap(), 4);
+        }
+
+        #[test]
+        fn test_drop_newest() {
+                let (tx, rx) = bounded_with_policy(2, OverflowPolicy::DropNewest);
+                for i in 0..5 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.recv().unwrap(), 0);
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelEmpty)));
+        }
+
+        #[test]
+        fn test_recv_min() {
+                let (tx, rx) = unbounded();
+                let timeout = Duration::from_millis(50);
+                assert!(matches!(rx.recv_min(3, timeout), Err(ChannelError::Timeout)));
+
+                tx.send(1).unwrap();
+                assert_eq!(rx.recv_min(3, timeout).unwrap(), vec![1]);
+
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..4 {
+                                tx.send(i).unwrap();
+                        }
+                });
+                tx_thread.join().unwrap();
+                assert_eq!(rx.recv_min(3, Duration::from_secs(5)).unwrap(), vec![0, 1, 2, 3]);
+                assert!(matches!(rx.recv_min(3, timeout), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_send_after_receiver_dropped() {
+                let (tx, rx) = unbounded();
+                let rx2 = rx.clone();
+                tx.send(1).unwrap();
+                drop(rx);
+                tx.send(2).unwrap();
+                drop(rx2);
+                let err = tx.send(3).unwrap_err();
+                assert!(matches!(err.error, ChannelError::NoReceivers));
+                assert_eq!(err.into_inner(), 3);
+        }
+
+        #[test]
+        fn test_receiver_drop_wakes_blocked_sender() {
+                let (tx, rx) = bounded(1);
+                tx.send(1).unwrap();
+                let tx_thread = thread::spawn(move || tx.send(2));
+                thread::sleep(Duration::from_millis(20));
+                drop(rx);
+                let err = tx_thread.join().unwrap().unwrap_err();
+                assert!(matches!(err.error, ChannelError::NoReceivers));
+        }
+
+        #[test]
+        fn test_send_batch_wakes_all_receivers() {
+                let (tx, rx) = unbounded();
+                let (parked_tx, parked_rx) = std::sync::mpsc::channel();
+                let workers: Vec<_> = (0..4)
+                        .map(|_| {
+                                let rx = rx.clone();
+                                let parked_tx = parked_tx.clone();
+                                thread::spawn(move || {
+                                        parked_tx.send(()).unwrap();
+                                        rx.recv().unwrap()
+                                })
+                        })
+                        .collect();
+                for _ in 0..4 {
+                        parked_rx.recv().unwrap();
+                }
+                thread::sleep(Duration::from_millis(20));
+
+                tx.send_batch(vec![1, 2, 3, 4]).unwrap();
+                let mut items: Vec<i32> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+                items.sort();
+                assert_eq!(items, vec![1, 2, 3, 4]);
+        }
+
+        #[test]
+        fn test_notify_strategy() {
+                let (tx, rx) = unbounded::<i32>();
+                assert_eq!(tx.state.notify_strategy(1), NotifyStrategy::One);
+                assert_eq!(tx.state.notify_strategy(2), NotifyStrategy::All);
+                let _rx2 = rx.clone();
+                assert_eq!(tx.state.notify_strategy(1), NotifyStrategy::All);
+        }
+
+        #[test]
+        fn test_send_batch_bounded() {
+                let (mut tx, rx) = bounded(2);
+                let rx_thread = thread::spawn(move || {
+                        let mut items = Vec::new();
+                        while let Ok(item) = rx.recv() {
+                                items.push(item);
+                        }
+                        items
+                });
+                tx.send_batch((0..10).collect()).unwrap();
+                tx.close().unwrap();
+                assert!(matches!(tx.send_batch(vec![10, 11]), Err(SendError { ref item, .. }) if *item == vec![10, 11]));
+                assert_eq!(rx_thread.join().unwrap(), (0..10).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_try_recv_drains_closed_channel() {
+                let (mut tx, rx) = unbounded();
+                for i in 0..3 {
+                        tx.send(i).unwrap();
+                }
+                tx.close().unwrap();
+                assert_eq!(rx.try_recv().unwrap(), 0);
+                assert_eq!(rx.try_recv().unwrap(), 1);
+                assert_eq!(rx.try_recv().unwrap(), 2);
+                assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_take_all() {
+                let (mut tx, rx) = unbounded();
+                for i in 0..3 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.take_all(), VecDeque::from(vec![0, 1, 2]));
+                tx.send(3).unwrap();
+                assert_eq!(rx.recv().unwrap(), 3);
+                tx.close().unwrap();
+                assert!(rx.take_all().is_empty());
+        }
+
+        #[test]
+        fn test_recv_opt_loop() {
+                let (tx, rx) = unbounded();
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..10 {
+                                tx.send(i).unwrap();
+                        }
+                });
+                let mut sum = 0;
+                while let Some(item) = rx.recv_opt() {
+                        sum += item;
+                }
+                tx_thread.join().unwrap();
+                assert_eq!(sum, 45);
+                assert_eq!(rx.recv_opt(), None);
+        }
+
+        #[test]
+        fn test_spin_channel() {
+                // set the spin count directly so the spin path runs even on one core
+                let mut state = SharedState::new(VecDeque::new());
+                state.spins = 100;
+                let (tx, rx): (Sender<usize>, Receiver<usize>) = state.into_channel();
+                let tx_thread = thread::spawn(move || {
+                        for burst in 0..10 {
+                                for i in 0..10 {
+                                        tx.send(burst * 10 + i).unwrap();
+                                }
+                                thread::sleep(Duration::from_millis(1));
+                        }
+                });
+                for i in 0..100 {
+                        assert_eq!(rx.recv().unwrap(), i);
+                }
+                tx_thread.join().unwrap();
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_status() {
+                let (mut tx, rx) = unbounded();
+                assert_eq!(rx.status(), ChannelStatus::Open);
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+                assert_eq!(rx.status(), ChannelStatus::Open);
+                tx.close().unwrap();
+                assert_eq!(rx.status(), ChannelStatus::ClosedNonEmpty(2));
+                rx.recv().unwrap();
+                assert_eq!(rx.status(), ChannelStatus::ClosedNonEmpty(1));
+                rx.recv().unwrap();
+                assert_eq!(rx.status(), ChannelStatus::ClosedEmpty);
+        }
+
+        #[test]
+        fn test_try_send_full() {
+                let (tx, rx) = bounded(1);
+                tx.try_send(1).unwrap();
+                let err = tx.try_send(2).unwrap_err();
+                assert!(matches!(err.error, ChannelError::Full));
+                assert_eq!(err.into_inner(), 2);
+                assert_eq!(rx.recv().unwrap(), 1);
+                tx.try_send(3).unwrap();
+        }
+
+        #[test]
+        fn test_send_blocking_backoff_slow_consumer() {
+                let (tx, rx) = bounded(2);
+                let rx_thread = thread::spawn(move || {
+                        let mut items = Vec::new();
+                        while let Ok(item) = rx.recv() {
+                                items.push(item);
+                                thread::sleep(Duration::from_millis(2));
+                        }
+                        items
+                });
+                for i in 0..20 {
+                        tx.send_blocking_backoff(i, Duration::from_secs(5)).unwrap();
+                }
+                drop(tx);
+                assert_eq!(rx_thread.join().unwrap(), (0..20).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_send_blocking_backoff_gives_up() {
+                let (tx, _rx) = bounded(1);
+                tx.send(1).unwrap();
+                let start = Instant::now();
+                let err = tx.send_blocking_backoff(2, Duration::from_millis(30)).unwrap_err();
+                assert!(start.elapsed() >= Duration::from_millis(30));
+                assert!(matches!(err.error, ChannelError::Timeout));
+                assert_eq!(err.into_inner(), 2);
+        }
+
+        #[test]
+        fn test_named_channel_debug() {
+                let (tx, rx) = channel_named::<i32>("task-queue");
+                tx.send(1).unwrap();
+                assert_eq!(format!("{:?}", tx), "Sender { name: \"task-queue\", queued: 1, closed: false, senders: 1 }");
+                assert_eq!(format!("{:?}", rx), "Receiver { name: \"task-queue\", queued: 1, closed: false, senders: 1 }");
+        }
+
+        #[test]
+        fn test_try_peek_decide() {
+                let (mut tx, rx) = unbounded();
+                assert!(matches!(rx.try_peek_decide(|_: &i32| (true, ())), Err(ChannelError::ChannelEmpty)));
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+
+                // leave it queued
+                assert_eq!(rx.try_peek_decide(|x| (false, *x * 10)).unwrap(), 10);
+                // take it
+                assert_eq!(rx.try_peek_decide(|x| (true, *x * 10)).unwrap(), 10);
+                assert_eq!(rx.try_peek_decide(|x| (*x == 2, *x)).unwrap(), 2);
+
+                tx.close().unwrap();
+                assert!(matches!(rx.try_peek_decide(|_| (true, ())), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        #[allow(deprecated)]
+        fn test_channel_is_unbounded() {
+                let (tx, rx) = channel();
+                assert_eq!(tx.state.capacity, None);
+                for i in 0..1000 {
+                        tx.try_send(i).unwrap();
+                }
+                assert_eq!(rx.recv().unwrap(), 0);
+        }
+
+        #[test]
+        fn test_wait_for_senders() {
+                let (tx, rx) = unbounded::<usize>();
+                let registrar = thread::spawn(move || {
+                        let mut workers = vec![tx];
+                        for _ in 0..3 {
+                                thread::sleep(Duration::from_millis(5));
+                                workers.push(workers[0].clone());
+                        }
+                        workers
+                });
+                rx.wait_for_senders(4, Duration::from_secs(5)).unwrap();
+                assert!(rx.state.num_senders.load(Ordering::Relaxed) >= 4);
+                assert!(matches!(rx.wait_for_senders(10, Duration::from_millis(10)), Err(ChannelError::Timeout)));
+
+                drop(registrar.join().unwrap());
+                assert!(matches!(rx.wait_for_senders(10, Duration::from_secs(5)), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_recv_batch() {
+                let (tx, rx) = unbounded();
+                assert!(matches!(rx.recv_batch(4, Duration::from_millis(10)), Err(ChannelError::Timeout)));
+
+                // a full batch returns right away and leaves the rest queued
+                for i in 0..6 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.recv_batch(4, Duration::from_secs(60)).unwrap(), vec![0, 1, 2, 3]);
+
+                // a partial batch waits out the window measured from the first item
+                let start = Instant::now();
+                assert_eq!(rx.recv_batch(4, Duration::from_millis(30)).unwrap(), vec![4, 5]);
+                assert!(start.elapsed() >= Duration::from_millis(30));
+
+                let tx_thread = thread::spawn(move || {
+                        thread::sleep(Duration::from_millis(20));
+                        tx.send(6).unwrap();
+                        thread::sleep(Duration::from_millis(5));
+                        tx.send(7).unwrap();
+                });
+                assert_eq!(rx.recv_batch(2, Duration::from_secs(60)).unwrap(), vec![6, 7]);
+                tx_thread.join().unwrap();
+                assert!(matches!(rx.recv_batch(2, Duration::from_secs(60)), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_finish_drains_backlog() {
+                let (tx, rx) = unbounded();
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+                tx.finish();
+                assert!(matches!(tx.send(3), Err(SendError { item: 3, error: ChannelError::ChannelClosed })));
+                assert_eq!(rx.status(), ChannelStatus::ClosedNonEmpty(2));
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert_eq!(rx.recv().unwrap(), 2);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+                assert_eq!(rx.close_reason(), None);
+        }
+
+        #[test]
+        fn test_finish_wakes_blocked_recv() {
+                let (tx, rx) = unbounded();
+                let rx_thread = thread::spawn(move || {
+                        let mut items = Vec::new();
+                        while let Ok(item) = rx.recv() {
+                                items.push(item);
+                        }
+                        items
+                });
+                // keep a clone alive so only `finish` can end the loop
+                let tx2 = tx.clone();
+                for i in 0..3 {
+                        tx.send(i).unwrap();
+                        thread::sleep(Duration::from_millis(5));
+                }
+                tx2.finish();
+                assert_eq!(rx_thread.join().unwrap(), vec![0, 1, 2]);
+                drop(tx);
+        }
+
+        #[test]
+        fn test_iter_chunks() {
+                let (mut tx, rx) = unbounded();
+                for i in 0..7 {
+                        tx.send(i).unwrap();
+                }
+                // close with a partial chunk still queued
+                tx.close().unwrap();
+                let chunks: Vec<_> = rx.iter_chunks(3).collect();
+                assert_eq!(chunks, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+        }
+
+        #[test]
+        fn test_iter_chunks_blocks_for_items() {
+                let (tx, rx) = bounded(2);
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..10 {
+                                tx.send(i).unwrap();
+                        }
+                });
+                let chunks: Vec<Vec<i32>> = rx.iter_chunks(4).collect();
+                tx_thread.join().unwrap();
+                assert!(chunks.iter().all(|chunk| !chunk.is_empty() && chunk.len() <= 2));
+                assert_eq!(chunks.concat(), (0..10).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_waiting_count() {
+                let (tx, rx) = unbounded();
+                let rx2 = rx.clone();
+                assert_eq!(rx.waiting_count(), 0);
+                let rx_thread = thread::spawn(move || rx2.recv().unwrap());
+                while rx.waiting_count() == 0 {
+                        thread::yield_now();
+                }
+                assert_eq!(rx.waiting_count(), 1);
+                tx.send(5).unwrap();
+                assert_eq!(rx_thread.join().unwrap(), 5);
+                assert_eq!(rx.waiting_count(), 0);
+        }
+
+        #[test]
+        fn test_recv_while_front_run() {
+                let (mut tx, rx) = unbounded();
+                for (key, value) in [("a", 1), ("a", 2), ("b", 3), ("a", 4)] {
+                        tx.send((key, value)).unwrap();
+                }
+                assert_eq!(rx.recv_while(|item| item.0 == "a").unwrap(), vec![("a", 1), ("a", 2)]);
+                assert_eq!(rx.recv_while(|item| item.0 == "a").unwrap(), vec![]);
+                assert_eq!(rx.recv_while(|item| item.0 == "b").unwrap(), vec![("b", 3)]);
+                tx.close().unwrap();
+                assert_eq!(rx.recv_while(|_| true).unwrap(), vec![("a", 4)]);
+                assert!(matches!(rx.recv_while(|_| true), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_watermark_hysteresis() {
+                let fired = Arc::new(Mutex::new(Vec::new()));
+                let log = fired.clone();
+                let (tx, rx) = channel_with_watermark(4, 1, move |len| log.lock().push(len));
+                for i in 0..8 {
+                        tx.send(i).unwrap();
+                }
+                // crossed once, staying above doesn't fire again
+                assert_eq!(*fired.lock(), vec![5]);
+
+                // dropping below high but not to low keeps it disarmed
+                for _ in 0..5 {
+                        rx.recv().unwrap();
+                }
+                tx.send(8).unwrap();
+                tx.send(9).unwrap();
+                assert_eq!(*fired.lock(), vec![5]);
+
+                // back down at low, the next crossing fires again
+                for _ in 0..5 {
+                        rx.recv().unwrap();
+                }
+                tx.send(10).unwrap();
+                assert!(!tx.state.watermark_active.load(Ordering::Relaxed));
+                tx.send_batch(vec![11, 12, 13, 14]).unwrap();
+                assert_eq!(*fired.lock(), vec![5, 5]);
+                assert!(tx.state.watermark_active.load(Ordering::Relaxed));
+        }
+
+        #[test]
+        fn test_recv_timed() {
+                let (tx, rx) = unbounded();
+                tx.send(1).unwrap();
+                let (item, waited) = rx.recv_timed().unwrap();
+                assert_eq!(item, 1);
+                assert!(waited < Duration::from_secs(1));
+
+                let tx_thread = thread::spawn(move || {
+                        thread::sleep(Duration::from_millis(20));
+                        tx.send(2).unwrap();
+                });
+                let (item, waited) = rx.recv_timed().unwrap();
+                assert_eq!(item, 2);
+                assert!(waited >= Duration::from_millis(20));
+                tx_thread.join().unwrap();
+                assert!(matches!(rx.recv_timed(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_work_stealing_balances_receivers() {
+                let (tx, rx) = work_stealing_channel();
+                let workers: Vec<_> = (0..4).map(|_| {
+                        let rx = rx.clone();
+                        thread::spawn(move || {
+                                let mut served = 0;
+                                while rx.recv().is_ok() {
+                                        served += 1;
+                                }
+                                served
+                        })
+                }).collect();
+                drop(rx);
+                for i in 0..2000 {
+                        tx.send(i).unwrap();
+                        if i % 10 == 0 {
+                                thread::yield_now();
+                        }
+                }
+                drop(tx);
+
+                let served: Vec<usize> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+                assert_eq!(served.iter().sum::<usize>(), 2000);
+                // nobody should be starved, even on a single core
+                assert!(served.iter().all(|&count| count >= 2000 / 16), "{:?}", served);
+        }
+
+        #[test]
+        fn test_reserve_avoids_reallocation() {
+                let (tx, rx) = channel_with_capacity(64);
+                let reserved = rx.state.elements.lock().capacity();
+                assert!(reserved >= 64);
+                for i in 0..64 {
+                        tx.send(i).unwrap();
+                }
+                assert_eq!(rx.state.elements.lock().capacity(), reserved);
+                assert_eq!(rx.state.capacity, None);
+
+                tx.reserve(1000);
+                let reserved = rx.state.elements.lock().capacity();
+                assert!(reserved >= 1064);
+                for i in 0..1000 {
+                        tx.try_send(i).unwrap();
+                }
+                assert_eq!(rx.state.elements.lock().capacity(), reserved);
+        }
+
+        #[test]
+        fn test_close_and_join_waits_for_drain() {
+                let (tx, rx) = unbounded();
+                for i in 0..5 {
+                        tx.send(i).unwrap();
+                }
+                let rx_thread = thread::spawn(move || {
+                        let mut items = Vec::new();
+                        while let Ok(item) = rx.recv() {
+                                thread::sleep(Duration::from_millis(2));
+                                items.push(item);
+                        }
+                        items
+                });
+                tx.close_and_join(Duration::from_secs(5)).unwrap();
+                assert!(matches!(tx.send(5), Err(SendError { error: ChannelError::ChannelClosed, .. })));
+                assert_eq!(rx_thread.join().unwrap(), vec![0, 1, 2, 3, 4]);
+        }
+
+        #[test]
+        fn test_close_and_join_timeout() {
+                let (tx, rx) = unbounded();
+                tx.send(1).unwrap();
+                assert!(matches!(tx.close_and_join(Duration::from_millis(10)), Err(ChannelError::Timeout)));
+                assert_eq!(rx.recv().unwrap(), 1);
+                tx.close_and_join(Duration::from_millis(10)).unwrap();
+
+                // nobody left to drain the queue
+                let (tx, rx) = unbounded();
+                tx.send(1).unwrap();
+                drop(rx);
+                assert!(matches!(tx.close_and_join(Duration::from_secs(5)), Err(ChannelError::NoReceivers)));
+        }
+
+        #[test]
+        fn test_recv_guard_requeue() {
+                let (tx, rx) = unbounded();
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+
+                let guard = rx.recv_guard().unwrap();
+                assert_eq!(*guard.item(), 1);
+                guard.requeue();
+                // requeued items go back in front, keeping FIFO order
+                let guard = rx.recv_guard().unwrap();
+                assert_eq!(*guard.item(), 1);
+                drop(guard);
+
+                assert_eq!(rx.recv().unwrap(), 2);
+                assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelEmpty)));
+        }
+
+        #[test]
+        fn test_recv_guard_requeue_wakes_receiver() {
+                let (tx, rx) = unbounded();
+                let rx2 = rx.clone();
+                tx.send("job").unwrap();
+                let guard = rx.recv_guard().unwrap();
+                let rx_thread = thread::spawn(move || rx2.recv().unwrap());
+                thread::sleep(Duration::from_millis(20));
+                guard.requeue();
+                assert_eq!(rx_thread.join().unwrap(), "job");
+        }
+
+        #[test]
+        fn test_high_water_mark() {
+                let (tx, rx) = bounded(16);
+                assert_eq!(rx.high_water_mark(), 0);
+                for burst in [3, 7, 5] {
+                        for i in 0..burst {
+                                tx.send(i).unwrap();
+                        }
+                        while rx.try_recv().is_ok() {}
+                }
+                assert_eq!(rx.high_water_mark(), 7);
+
+                tx.send_batch((0..10).collect()).unwrap();
+                assert_eq!(rx.high_water_mark(), 10);
+                rx.recv().unwrap();
+                assert_eq!(rx.high_water_mark(), 10);
+        }
+
+        #[test]
+        fn test_panicked_sender_poisons_channel() {
+                let (tx, rx) = channel_with_observer(|item: &i32| {
+                        if *item == 3 {
+                                panic!("observer failed on {}", item);
+                        }
+                });
+                let tx2 = tx.clone();
+                let producer = thread::spawn(move || {
+                        for i in 0..5 {
+                                tx.send(i).unwrap();
+                        }
+                });
+                assert!(producer.join().is_err());
+
+                // the queue survives the panic, but the other sender can't add to it
+                #[cfg(not(feature = "parking_lot"))]
+                {
+                        let err = tx2.send(10).unwrap_err();
+                        assert!(matches!(err, SendError { item: 10, error: ChannelError::Poisoned }));
+                        assert_eq!(tx2.try_send_batch(vec![11, 12]).unwrap_err(), vec![11, 12]);
+                }
+                assert_eq!(rx.recv().unwrap(), 0);
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert_eq!(rx.recv().unwrap(), 2);
+                #[cfg(not(feature = "parking_lot"))]
+                {
+                        assert!(matches!(rx.try_recv(), Err(ChannelError::Poisoned)));
+                        assert!(matches!(rx.recv(), Err(ChannelError::Poisoned)));
+                }
+                #[cfg(feature = "parking_lot")]
+                {
+                        drop(tx2);
+                        assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+                }
+        }
+
+        #[test]
+        #[cfg(not(feature = "parking_lot"))]
+        fn test_poison_wakes_parked_receiver() {
+                let (tx, rx) = channel_with_observer(|_: &i32| panic!("observer failed"));
+                let _tx2 = tx.clone();
+                let rx_thread = thread::spawn(move || rx.recv());
+                thread::sleep(Duration::from_millis(20));
+                assert!(thread::spawn(move || tx.send(1)).join().is_err());
+                assert!(matches!(rx_thread.join().unwrap(), Err(ChannelError::Poisoned)));
+        }
+
+        #[test]
+        #[cfg(not(feature = "parking_lot"))]
+        fn test_poison_from_borrowed_sender_wakes_receiver() {
+                let (tx, rx) = channel_with_observer(|_: &i32| panic!("observer failed"));
+                let rx_thread = thread::spawn(move || rx.recv());
+                thread::sleep(Duration::from_millis(20));
+                // the panicking thread only borrows the sender, so no sender drops while unwinding
+                thread::scope(|s| {
+                        assert!(s.spawn(|| tx.send(1)).join().is_err());
+                });
+                assert!(matches!(rx_thread.join().unwrap(), Err(ChannelError::Poisoned)));
+                drop(tx);
+        }
+
+        #[test]
+        #[cfg(not(feature = "parking_lot"))]
+        fn test_every_recv_reports_poison() {
+                let (tx, rx) = channel_with_observer(|_: &i32| panic!("observer failed"));
+                thread::scope(|s| {
+                        assert!(s.spawn(|| tx.send(1)).join().is_err());
+                });
+                let cancel = Arc::new(AtomicBool::new(false));
+                let shutdown = crate::signal::ShutdownSignal::new();
+                assert!(matches!(rx.poll_recv(Duration::from_millis(10)), Err(ChannelError::Poisoned)));
+                assert!(matches!(rx.recv_interruptible(&cancel), Err(ChannelError::Poisoned)));
+                assert!(matches!(rx.recv_matching(|_| true), Err(ChannelError::Poisoned)));
+                assert!(matches!(rx.recv_min(1, Duration::from_secs(5)), Err(ChannelError::Poisoned)));
+                assert!(matches!(rx.recv_batch(1, Duration::from_secs(5)), Err(ChannelError::Poisoned)));
+                assert!(matches!(rx.try_peek_decide(|_| (true, ())), Err(ChannelError::Poisoned)));
+                assert!(matches!(
+                        rx.recv_or_event(&shutdown),
+                        Err(crate::signal::RecvOrEvent::Channel(ChannelError::Poisoned))
+                ));
+                drop(tx);
+        }
+
+        #[test]
+        fn test_recv_batch_item_taken_by_other_receiver() {
+                let (tx, rx) = unbounded();
+                let rx2 = rx.clone();
+                tx.send(1).unwrap();
+                let batcher = thread::spawn(move || rx.recv_batch(4, Duration::from_millis(50)));
+                // let the batcher see the first item and open its window, then steal it
+                while rx2.waiting_count() == 0 {
+                        thread::yield_now();
+                }
+                assert_eq!(rx2.try_recv().unwrap(), 1);
+                assert!(matches!(batcher.join().unwrap(), Err(ChannelError::Timeout)));
+                drop(tx);
+        }
+
+        #[test]
+        fn test_watermark_rearms_with_zero_low() {
+                let fired = Arc::new(AtomicUsize::new(0));
+                let count = fired.clone();
+                let (tx, rx) = channel_with_watermark(2, 0, move |_| {
+                        count.fetch_add(1, Ordering::Relaxed);
+                });
+                for round in 0..3 {
+                        for i in 0..3 {
+                                tx.send(i).unwrap();
+                        }
+                        while rx.try_recv().is_ok() {}
+                        assert_eq!(fired.load(Ordering::Relaxed), round + 1);
+                }
+        }
+
+        #[test]
+        #[cfg(not(feature = "parking_lot"))]
+        fn test_panicking_predicate_wakes_parked_receiver() {
+                let (tx, rx) = unbounded();
+                let rx2 = rx.clone();
+                let rx_thread = thread::spawn(move || rx2.recv_matching(|item: &i32| *item == 2));
+                tx.send(1).unwrap();
+                thread::sleep(Duration::from_millis(20));
+                thread::scope(|s| {
+                        assert!(s.spawn(|| rx.recv_matching(|_: &i32| panic!("predicate failed"))).join().is_err());
+                });
+                assert!(matches!(rx_thread.join().unwrap(), Err(ChannelError::Poisoned)));
+                drop(tx);
+        }
+
+        #[test]
+        fn test_wait_for_senders_returns_on_close() {
+                let (tx, rx) = unbounded::<i32>();
+                let start = Instant::now();
+                let tx_thread = thread::spawn(move || {
+                        thread::sleep(Duration::from_millis(20));
+                        drop(tx);
+                });
+                let result = rx.wait_for_senders(2, Duration::from_secs(3));
+                assert!(matches!(result, Err(ChannelError::ChannelClosed)));
+                assert!(start.elapsed() < Duration::from_secs(1));
+                tx_thread.join().unwrap();
+        }
+
+        #[test]
+        fn test_deadline_timer_closes_idle_channel() {
+                let (tx, _rx) = channel_with_deadline::<i32>(Duration::from_millis(20));
+                thread::sleep(Duration::from_millis(100));
+                // read the flag directly, `is_closed` would close the channel itself
+                assert!(tx.state.closed.load(Ordering::Relaxed));
+        }
+
+        #[test]
+        #[should_panic(expected = "capacity must be non-zero")]
+        fn test_zero_capacity_panics() {
+                let _ = bounded::<i32>(0);
+        }
+
+}
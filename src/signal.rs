@@ -0,0 +1,134 @@
+use crate::shared_state::ChannelError;
+use crate::queue::Queue;
+use crate::receiver::Receiver;
+use std::sync::{Arc, Mutex, Condvar};
+use std::time::Duration;
+
+/// How often a blocked `recv_or_event` wakes up to check the signal
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A shutdown flag threads can wait on, shareable with code that already uses
+/// the `Arc<(Mutex<bool>, Condvar)>` idiom
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+        pub inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl ShutdownSignal {
+        /// A signal that hasn't been triggered yet
+        pub fn new() -> Self {
+                Self::default()
+        }
+
+        /// Set the flag and wake everyone waiting on it
+        pub fn trigger(&self) {
+                let (triggered, condvar) = &*self.inner;
+                *triggered.lock().unwrap() = true;
+                condvar.notify_all();
+        }
+
+        pub fn is_triggered(&self) -> bool {
+                *self.inner.0.lock().unwrap()
+        }
+
+        /// Block until the signal is triggered
+        pub fn wait(&self) {
+                let (triggered, condvar) = &*self.inner;
+                let mut triggered = triggered.lock().unwrap();
+                while !*triggered {
+                        triggered = condvar.wait(triggered).unwrap();
+                }
+        }
+}
+
+impl From<Arc<(Mutex<bool>, Condvar)>> for ShutdownSignal {
+        fn from(inner: Arc<(Mutex<bool>, Condvar)>) -> Self {
+                ShutdownSignal { inner }
+        }
+}
+
+/// Why `recv_or_event` returned without an item
+#[derive(Debug)]
+pub enum RecvOrEvent {
+        /// The shutdown signal was triggered
+        Interrupted,
+        /// The receive itself failed
+        Channel(ChannelError),
+}
+
+impl From<ChannelError> for RecvOrEvent {
+        fn from(err: ChannelError) -> Self {
+                RecvOrEvent::Channel(err)
+        }
+}
+
+impl<T, Q: Queue<T>> Receiver<T, Q> {
+        /// Like `recv`, but gives up with `Interrupted` once `event` is triggered,
+        /// without closing the channel. The signal is polled every few milliseconds
+        pub fn recv_or_event(&self, event: &ShutdownSignal) -> Result<T, RecvOrEvent> {
+                let mut elements = self.state.elements.lock();
+                loop {
+                        if event.is_triggered() {
+                                return Err(RecvOrEvent::Interrupted);
+                        }
+                        if let Some(item) = self.pop(&mut elements) {
+                                return Ok(item);
+                        }
+                        if let Some(error) = self.state.empty_error() {
+                                return Err(error.into());
+                        }
+                        elements = self.park(elements, Some(EVENT_POLL_INTERVAL));
+                }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::shared_state::unbounded;
+        use std::thread;
+
+        #[test]
+        fn test_recv_or_event() {
+                let (mut tx, rx) = unbounded();
+                let shutdown = ShutdownSignal::new();
+                tx.send(1).unwrap();
+                assert_eq!(rx.recv_or_event(&shutdown).unwrap(), 1);
+
+                let signal = shutdown.clone();
+                let rx_thread = thread::spawn(move || {
+                        let result = rx.recv_or_event(&signal).map(|_| ());
+                        (rx, result)
+                });
+                thread::sleep(Duration::from_millis(20));
+                shutdown.trigger();
+                let (rx, result) = rx_thread.join().unwrap();
+                assert!(matches!(result, Err(RecvOrEvent::Interrupted)));
+
+                // the channel is still open
+                tx.send(2).unwrap();
+                tx.close().unwrap();
+                assert_eq!(rx.recv().unwrap(), 2);
+        }
+
+        #[test]
+        fn test_existing_condvar_signal() {
+                let app_signal = Arc::new((Mutex::new(false), Condvar::new()));
+                let shutdown = ShutdownSignal::from(app_signal.clone());
+                let (tx, rx) = unbounded::<i32>();
+                let rx_thread = thread::spawn(move || rx.recv_or_event(&shutdown));
+
+                *app_signal.0.lock().unwrap() = true;
+                app_signal.1.notify_all();
+                assert!(matches!(rx_thread.join().unwrap(), Err(RecvOrEvent::Interrupted)));
+                drop(tx);
+        }
+
+        #[test]
+        fn test_closed_channel() {
+                let (tx, rx) = unbounded::<i32>();
+                drop(tx);
+                let result = rx.recv_or_event(&ShutdownSignal::new());
+                assert!(matches!(result, Err(RecvOrEvent::Channel(ChannelError::ChannelClosed))));
+        }
+}
@@ -0,0 +1,128 @@
+use crate::queue::Queue;
+use crate::receiver::Receiver;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Adapts a `Receiver` into a `Stream` that ends once the channel is closed and empty
+pub struct ReceiverStream<T, Q = VecDeque<T>> {
+        pub receiver: Receiver<T, Q>
+}
+
+impl<T, Q> ReceiverStream<T, Q> {
+        pub fn new(receiver: Receiver<T, Q>) -> Self {
+                ReceiverStream { receiver }
+        }
+
+        /// Get the wrapped receiver back
+        pub fn into_inner(self) -> Receiver<T, Q> {
+                self.receiver
+        }
+}
+
+impl<T, Q: Queue<T>> Stream for ReceiverStream<T, Q> {
+        type Item = T;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+                let receiver = &self.get_mut().receiver;
+                let state = &receiver.state;
+                let mut elements = state.elements.lock();
+                if let Some(item) = receiver.pop(&mut elements) {
+                        return Poll::Ready(Some(item));
+                }
+                // a poisoned channel ends the stream too, there is no way to report it
+                if state.empty_error().is_some() {
+                        return Poll::Ready(None);
+                }
+
+                // register while still holding the queue lock, so a send can't slip in
+                // between the empty check and the registration and go unnoticed
+                let mut wakers = state.wakers.lock();
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                        wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::shared_state::{unbounded, SharedState};
+        use futures::executor::block_on;
+        use futures::StreamExt;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::task::{Wake, Waker};
+        use std::thread;
+        use std::time::Duration;
+
+        #[test]
+        fn test_stream_collects_all_items() {
+                let (tx, rx) = unbounded();
+                let tx_thread = thread::spawn(move || {
+                        for i in 0..50 {
+                                tx.send(i).unwrap();
+                                if i % 10 == 0 {
+                                        thread::sleep(Duration::from_millis(5));
+                                }
+                        }
+                });
+                let items: Vec<i32> = block_on(ReceiverStream::new(rx).collect());
+                tx_thread.join().unwrap();
+                assert_eq!(items, (0..50).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_stream_combinators() {
+                let (tx, rx) = unbounded();
+                for i in 0..10 {
+                        tx.send(i).unwrap();
+                }
+                drop(tx);
+                let evens: Vec<i32> = block_on(ReceiverStream::new(rx).filter(|x| std::future::ready(x % 2 == 0)).collect());
+                assert_eq!(evens, vec![0, 2, 4, 6, 8]);
+        }
+
+        #[test]
+        #[cfg(not(feature = "parking_lot"))]
+        fn test_poison_ends_pending_stream() {
+                let (tx, rx) = crate::shared_state::channel_with_observer(|_: &i32| panic!("observer failed"));
+                let stream_thread = thread::spawn(move || block_on(ReceiverStream::new(rx).next()));
+                thread::sleep(Duration::from_millis(20));
+                thread::scope(|s| {
+                        assert!(s.spawn(|| tx.send(1)).join().is_err());
+                });
+                assert_eq!(stream_thread.join().unwrap(), None);
+                drop(tx);
+        }
+
+        #[test]
+        fn test_deadline_ends_pending_stream() {
+                let (tx, rx) = crate::shared_state::channel_with_deadline::<i32>(Duration::from_millis(20));
+                // nothing touches the channel, the deadline alone ends the stream
+                assert_eq!(block_on(ReceiverStream::new(rx).next()), None);
+                drop(tx);
+        }
+
+        #[test]
+        fn test_close_wakes_streams_outside_the_lock() {
+                struct LockingWaker(Arc<SharedState<i32>>, AtomicBool);
+                impl Wake for LockingWaker {
+                        fn wake(self: Arc<Self>) {
+                                // stands in for a waker that polls the stream inline
+                                drop(self.0.elements.lock());
+                                self.1.store(true, Ordering::Relaxed);
+                        }
+                }
+
+                let (mut tx, rx) = unbounded::<i32>();
+                let locking = Arc::new(LockingWaker(rx.state.clone(), AtomicBool::new(false)));
+                let waker = Waker::from(locking.clone());
+                let mut stream = ReceiverStream::new(rx);
+                assert!(Pin::new(&mut stream).poll_next(&mut Context::from_waker(&waker)).is_pending());
+                tx.close().unwrap();
+                assert!(locking.1.load(Ordering::Relaxed));
+        }
+}
@@ -0,0 +1,119 @@
+//! The locks used throughout the crate. They are `std::sync` by default, or
+//! `parking_lot` with the `parking_lot` feature. Either way `lock` hands back
+//! the guard directly, so call sites don't care which one they got.
+//!
+//! A panic while a std lock is held doesn't cascade into every later `lock`:
+//! the guard is recovered and `is_poisoned` remembers that it happened.
+//! `parking_lot` locks never poison
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::PoisonError;
+use std::time::Duration;
+
+#[cfg(not(feature = "parking_lot"))]
+pub use std::sync::MutexGuard;
+#[cfg(feature = "parking_lot")]
+pub use parking_lot::MutexGuard;
+
+#[cfg(not(feature = "parking_lot"))]
+type RawMutex<T> = std::sync::Mutex<T>;
+#[cfg(feature = "parking_lot")]
+type RawMutex<T> = parking_lot::Mutex<T>;
+
+#[cfg(not(feature = "parking_lot"))]
+type RawCondvar = std::sync::Condvar;
+#[cfg(feature = "parking_lot")]
+type RawCondvar = parking_lot::Condvar;
+
+/// Mutual exclusion lock over `T`
+#[derive(Default)]
+pub struct Mutex<T>(RawMutex<T>);
+
+/// Condition variable paired with a `Mutex`
+#[derive(Default)]
+pub struct Condvar(RawCondvar);
+
+impl<T> Mutex<T> {
+        pub fn new(value: T) -> Self {
+                Mutex(RawMutex::new(value))
+        }
+
+        /// Block until the lock is ours
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+                #[cfg(not(feature = "parking_lot"))]
+                return self.0.lock().unwrap_or_else(PoisonError::into_inner);
+                #[cfg(feature = "parking_lot")]
+                return self.0.lock();
+        }
+
+        /// Take the lock only if nobody else holds it
+        pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+                #[cfg(not(feature = "parking_lot"))]
+                return match self.0.try_lock() {
+                        Ok(guard) => Some(guard),
+                        Err(std::sync::TryLockError::WouldBlock) => None,
+                        Err(std::sync::TryLockError::Poisoned(err)) => Some(err.into_inner()),
+                };
+                #[cfg(feature = "parking_lot")]
+                return self.0.try_lock();
+        }
+
+        /// Whether a thread panicked while holding the lock
+        pub fn is_poisoned(&self) -> bool {
+                #[cfg(not(feature = "parking_lot"))]
+                return self.0.is_poisoned();
+                #[cfg(feature = "parking_lot")]
+                return false;
+        }
+
+        pub fn get_mut(&mut self) -> &mut T {
+                #[cfg(not(feature = "parking_lot"))]
+                return self.0.get_mut().unwrap_or_else(PoisonError::into_inner);
+                #[cfg(feature = "parking_lot")]
+                return self.0.get_mut();
+        }
+
+        pub fn into_inner(self) -> T {
+                #[cfg(not(feature = "parking_lot"))]
+                return self.0.into_inner().unwrap_or_else(PoisonError::into_inner);
+                #[cfg(feature = "parking_lot")]
+                return self.0.into_inner();
+        }
+}
+
+impl Condvar {
+        pub fn new() -> Self {
+                Condvar(RawCondvar::new())
+        }
+
+        /// Release the lock and park until notified, then take the lock back
+        pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+                #[cfg(not(feature = "parking_lot"))]
+                return self.0.wait(guard).unwrap_or_else(PoisonError::into_inner);
+                #[cfg(feature = "parking_lot")]
+                {
+                        let mut guard = guard;
+                        self.0.wait(&mut guard);
+                        guard
+                }
+        }
+
+        /// Like `wait`, but gives up after `timeout`
+        pub fn wait_timeout<'a, T>(&self, guard: MutexGuard<'a, T>, timeout: Duration) -> MutexGuard<'a, T> {
+                #[cfg(not(feature = "parking_lot"))]
+                return self.0.wait_timeout(guard, timeout).unwrap_or_else(PoisonError::into_inner).0;
+                #[cfg(feature = "parking_lot")]
+                {
+                        let mut guard = guard;
+                        self.0.wait_for(&mut guard, timeout);
+                        guard
+                }
+        }
+
+        pub fn notify_one(&self) {
+                self.0.notify_one();
+        }
+
+        pub fn notify_all(&self) {
+                self.0.notify_all();
+        }
+}
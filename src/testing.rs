@@ -0,0 +1,91 @@
+use crate::queue::Queue;
+use crate::sender::Sender;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An item that counts its own drops, for checking that channels don't leak
+pub(crate) struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+        fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+        }
+}
+
+/// Hands out `DropCounter`s and keeps track of how many were made and dropped
+#[derive(Default)]
+pub(crate) struct DropTally {
+        drops: Arc<AtomicUsize>,
+        made: usize,
+}
+
+impl DropTally {
+        /// A fresh item tied to this tally
+        pub(crate) fn item(&mut self) -> DropCounter {
+                self.made += 1;
+                DropCounter(self.drops.clone())
+        }
+
+        /// Send `n` fresh items on `tx`, returning how many went through
+        pub(crate) fn fill<Q: Queue<DropCounter>>(&mut self, tx: &Sender<DropCounter, Q>, n: usize) -> usize {
+                (0..n).filter(|_| tx.send(self.item()).is_ok()).count()
+        }
+
+        pub(crate) fn made(&self) -> usize {
+                self.made
+        }
+
+        pub(crate) fn dropped(&self) -> usize {
+                self.drops.load(Ordering::Relaxed)
+        }
+
+        /// Panic unless every item made so far has been dropped exactly once
+        pub(crate) fn assert_no_leaks(&self) {
+                assert_eq!(self.dropped(), self.made, "items leaked or were dropped twice");
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use crate::ring::ring_channel;
+        use crate::shared_state::{bounded_with_policy, unbounded, OverflowPolicy};
+
+        #[test]
+        fn test_unbounded_frees_buffered_items() {
+                let mut tally = DropTally::default();
+                let (tx, rx) = unbounded();
+                assert_eq!(tally.fill(&tx, 100), 100);
+                drop(rx.recv().unwrap());
+                assert_eq!(tally.dropped(), 1);
+                drop(tx);
+                drop(rx);
+                tally.assert_no_leaks();
+        }
+
+        #[test]
+        fn test_bounded_frees_overflow_and_buffered_items() {
+                let mut tally = DropTally::default();
+                let (tx, rx) = bounded_with_policy(4, OverflowPolicy::DropOldest);
+                assert_eq!(tally.fill(&tx, 10), 10);
+                assert_eq!(tally.dropped(), 6);
+                drop(rx);
+                drop(tx);
+                tally.assert_no_leaks();
+        }
+
+        #[test]
+        fn test_closed_with_items() {
+                let mut tally = DropTally::default();
+                let (mut tx, rx) = ring_channel(8);
+                assert_eq!(tally.fill(&tx, 5), 5);
+                tx.close().unwrap();
+                // sends after close hand the item back, which must free it too
+                assert_eq!(tally.fill(&tx, 3), 0);
+                assert_eq!(tally.made(), 8);
+                drop(rx.recv().unwrap());
+                drop(tx);
+                drop(rx);
+                tally.assert_no_leaks();
+        }
+}
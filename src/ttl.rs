@@ -0,0 +1,154 @@
+use crate::shared_state::{unbounded, ChannelError, SendError};
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Sender part of a channel whose items expire after a ttl
+pub struct TtlSender<T> {
+        pub inner: Sender<(Instant, T)>
+}
+
+/// Receiver part of a channel whose items expire after a ttl
+pub struct TtlReceiver<T> {
+        pub inner: Receiver<(Instant, T)>,
+        pub ttl: Duration,
+        pub dropped: AtomicUsize,
+}
+
+/// Create a channel where items older than `ttl` are skipped on receive
+pub fn channel_with_ttl<T>(ttl: Duration) -> (TtlSender<T>, TtlReceiver<T>) {
+        let (sender, receiver) = unbounded();
+        (
+                TtlSender { inner: sender },
+                TtlReceiver { inner: receiver, ttl, dropped: AtomicUsize::new(0) }
+        )
+}
+
+impl<T> TtlSender<T> {
+        /// Send an item on the channel, stamped with the current time
+        pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+                self.inner.send((Instant::now(), item))
+                        .map_err(|err| SendError { item: err.item.1, error: err.error })
+        }
+
+        /// Close the channel
+        pub fn close(&mut self) -> Result<(), ChannelError> {
+                self.inner.close()
+        }
+}
+
+impl<T> TtlReceiver<T> {
+        /// Block until a fresh item is available, skipping expired ones
+        pub fn recv(&self) -> Result<T, ChannelError> {
+                let state = &self.inner.state;
+                let mut elements = state.elements.lock();
+                loop {
+                        self.expire(&mut elements);
+                        if let Some((_, item)) = elements.pop_front() {
+                                state.notify_drained(elements.len());
+                                return Ok(item);
+                        }
+                        if let Some(error) = state.empty_error() {
+                                return Err(error);
+                        }
+                        elements = state.is_empty.wait(elements);
+                }
+        }
+
+        /// Try to receive a fresh item without blocking, skipping expired ones
+        pub fn try_recv(&self) -> Result<T, ChannelError> {
+                let state = &self.inner.state;
+                let Some(mut elements) = state.elements.try_lock() else {
+                        return Err(ChannelError::RecvBlocked);
+                };
+                self.expire(&mut elements);
+                if let Some((_, item)) = elements.pop_front() {
+                        state.notify_drained(elements.len());
+                        Ok(item)
+                } else {
+                        Err(state.empty_error().unwrap_or(ChannelError::ChannelEmpty))
+                }
+        }
+
+        /// Number of items that expired before they could be received
+        pub fn dropped(&self) -> usize {
+                self.dropped.load(Ordering::Relaxed)
+        }
+
+        // pop every expired item off the front of the queue
+        fn expire(&self, elements: &mut VecDeque<(Instant, T)>) {
+                let mut expired = 0;
+                while let Some((sent, _)) = elements.front() {
+                        if sent.elapsed() <= self.ttl {
+                                break;
+                        }
+                        elements.pop_front();
+                        expired += 1;
+                }
+                if expired > 0 {
+                        self.dropped.fetch_add(expired, Ordering::Relaxed);
+                        self.inner.state.notify_drained(elements.len());
+                }
+        }
+}
+
+impl<T> Clone for TtlSender<T> {
+        fn clone(&self) -> Self {
+                TtlSender { inner: self.inner.clone() }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use std::thread;
+
+        #[test]
+        fn test_expired_items_are_skipped() {
+                let (mut tx, rx) = channel_with_ttl(Duration::from_millis(20));
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+                thread::sleep(Duration::from_millis(40));
+                tx.send(3).unwrap();
+                assert_eq!(rx.recv().unwrap(), 3);
+                assert_eq!(rx.dropped(), 2);
+
+                tx.close().unwrap();
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_try_recv_fresh_items() {
+                let (tx, rx) = channel_with_ttl(Duration::from_secs(60));
+                tx.send("a").unwrap();
+                assert_eq!(rx.try_recv().unwrap(), "a");
+                assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelEmpty)));
+                assert_eq!(rx.dropped(), 0);
+        }
+
+        #[test]
+        fn test_cloned_senders() {
+                let (tx, rx) = channel_with_ttl(Duration::from_secs(60));
+                let tx2 = tx.clone();
+                tx.send(1).unwrap();
+                tx2.send(2).unwrap();
+                drop(tx);
+                drop(tx2);
+                assert_eq!(rx.recv().unwrap(), 1);
+                assert_eq!(rx.recv().unwrap(), 2);
+                assert!(matches!(rx.recv(), Err(ChannelError::ChannelClosed)));
+        }
+
+        #[test]
+        fn test_expiring_everything_counts_as_drained() {
+                let (tx, rx) = channel_with_ttl(Duration::from_millis(10));
+                tx.send(1).unwrap();
+                thread::sleep(Duration::from_millis(20));
+                let join = thread::spawn(move || tx.inner.close_and_join(Duration::from_secs(5)));
+                thread::sleep(Duration::from_millis(20));
+                assert!(matches!(rx.try_recv(), Err(ChannelError::ChannelClosed)));
+                assert!(join.join().unwrap().is_ok());
+        }
+}
@@ -0,0 +1,193 @@
+use crate::shared_state::{unbounded, ChannelError, SendError};
+use crate::sender::Sender;
+use crate::receiver::Receiver;
+use crate::sync::{Mutex, Condvar};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+/// Tracks the total weight of the items in flight on a weighted channel
+pub struct Budget<T> {
+        pub weight: Mutex<usize>,
+        pub max_weight: usize,
+        pub weigh: Box<dyn Fn(&T) -> usize + Send + Sync>,
+        pub released: Condvar,
+}
+
+/// Sender part of a weighted channel
+pub struct WeightedSender<T> {
+        pub inner: Sender<T>,
+        pub budget: Arc<Budget<T>>,
+}
+
+/// Receiver part of a weighted channel
+pub struct WeightedReceiver<T> {
+        // taken out in `drop`, so blocked senders are only woken once it is gone
+        inner: Option<Receiver<T>>,
+        pub budget: Arc<Budget<T>>,
+}
+
+/// Create a channel bounded by the total weight of its items rather than their count.
+///
+/// `weigh` is called on both ends, so it must give the same answer for an item
+/// every time. An item heavier than `max_weight` on its own is let through once
+/// the channel is otherwise empty, instead of blocking forever
+pub fn weighted_channel<T>(
+        max_weight: usize,
+        weigh: impl Fn(&T) -> usize + Send + Sync + 'static,
+) -> (WeightedSender<T>, WeightedReceiver<T>) {
+        let (sender, receiver) = unbounded();
+        let budget = Arc::new(Budget {
+                weight: Mutex::new(0),
+                max_weight,
+                weigh: Box::new(weigh),
+                released: Condvar::new(),
+        });
+        (
+                WeightedSender { inner: sender, budget: budget.clone() },
+                WeightedReceiver { inner: Some(receiver), budget }
+        )
+}
+
+impl<T> WeightedSender<T> {
+        /// Send an item, blocking until its weight fits in the budget
+        pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+                let item_weight = (self.budget.weigh)(&item);
+                let mut weight = self.budget.weight.lock();
+                loop {
+                        let state = &self.inner.state;
+                        if state.is_closed() {
+                                return Err(SendError { item, error: ChannelError::ChannelClosed });
+                        }
+                        if state.no_receivers.load(Ordering::Relaxed) {
+                                return Err(SendError { item, error: ChannelError::NoReceivers });
+                        }
+                        if *weight == 0 || *weight + item_weight <= self.budget.max_weight {
+                                break;
+                        }
+                        weight = self.budget.released.wait(weight);
+                }
+
+                // reserve the weight before the item becomes visible to the receiver
+                *weight += item_weight;
+                drop(weight);
+                self.inner.send(item).inspect_err(|_| self.release(item_weight))
+        }
+
+        /// Close the channel
+        pub fn close(&mut self) -> Result<(), ChannelError> {
+                self.inner.close()?;
+                let _weight = self.budget.weight.lock();
+                self.budget.released.notify_all();
+                Ok(())
+        }
+
+        fn release(&self, item_weight: usize) {
+                *self.budget.weight.lock() -= item_weight;
+                self.budget.released.notify_all();
+        }
+}
+
+impl<T> WeightedReceiver<T> {
+        /// Block until an item is available, giving its weight back to the senders
+        pub fn recv(&self) -> Result<T, ChannelError> {
+                let item = self.receiver().recv()?;
+                self.release(&item);
+                Ok(item)
+        }
+
+        /// Try to receive an item without blocking
+        pub fn try_recv(&self) -> Result<T, ChannelError> {
+                let item = self.receiver().try_recv()?;
+                self.release(&item);
+                Ok(item)
+        }
+
+        /// Total weight currently in flight
+        pub fn weight(&self) -> usize {
+                *self.budget.weight.lock()
+        }
+
+        fn receiver(&self) -> &Receiver<T> {
+                self.inner.as_ref().expect("receiver is only taken on drop")
+        }
+
+        fn release(&self, item: &T) {
+                let item_weight = (self.budget.weigh)(item);
+                *self.budget.weight.lock() -= item_weight;
+                self.budget.released.notify_all();
+        }
+}
+
+impl<T> Clone for WeightedSender<T> {
+        fn clone(&self) -> Self {
+                WeightedSender { inner: self.inner.clone(), budget: self.budget.clone() }
+        }
+}
+
+impl<T> Drop for WeightedReceiver<T> {
+        fn drop(&mut self) {
+                drop(self.inner.take());
+                // wake blocked senders so they notice nobody is left to receive
+                let _weight = self.budget.weight.lock();
+                self.budget.released.notify_all();
+        }
+}
+
+#[cfg(test)]
+mod tests {
+        use super::*;
+        use std::thread;
+        use std::time::Duration;
+
+        #[test]
+        fn test_weight_never_exceeds_cap() {
+                let (tx, rx) = weighted_channel(10, |item: &Vec<u8>| item.len());
+                let budget = rx.budget.clone();
+                let tx_thread = thread::spawn(move || {
+                        for len in [3, 7, 2, 5, 9, 1, 4, 6, 8, 10] {
+                                tx.send(vec![0; len]).unwrap();
+                                assert!(*budget.weight.lock() <= 10);
+                        }
+                });
+
+                let mut lens = Vec::new();
+                while let Ok(item) = rx.recv() {
+                        assert!(rx.weight() <= 10);
+                        lens.push(item.len());
+                        thread::sleep(Duration::from_millis(1));
+                }
+                tx_thread.join().unwrap();
+                assert_eq!(lens, vec![3, 7, 2, 5, 9, 1, 4, 6, 8, 10]);
+                assert_eq!(rx.weight(), 0);
+        }
+
+        #[test]
+        fn test_send_blocks_until_weight_is_released() {
+                let (tx, rx) = weighted_channel(5, |item: &usize| *item);
+                tx.send(4).unwrap();
+                let tx_thread = thread::spawn(move || tx.send(3));
+                thread::sleep(Duration::from_millis(20));
+                assert_eq!(rx.weight(), 4);
+                assert_eq!(rx.recv().unwrap(), 4);
+                tx_thread.join().unwrap().unwrap();
+                assert_eq!(rx.recv().unwrap(), 3);
+        }
+
+        #[test]
+        fn test_oversized_item_when_empty() {
+                let (tx, rx) = weighted_channel(5, |item: &usize| *item);
+                tx.send(8).unwrap();
+                assert_eq!(rx.try_recv().unwrap(), 8);
+        }
+
+        #[test]
+        fn test_drop_receiver_wakes_blocked_sender() {
+                let (tx, rx) = weighted_channel(5, |item: &usize| *item);
+                tx.send(4).unwrap();
+                let tx_thread = thread::spawn(move || tx.send(3));
+                thread::sleep(Duration::from_millis(20));
+                drop(rx);
+                let err = tx_thread.join().unwrap().unwrap_err();
+                assert!(matches!(err, SendError { item: 3, error: ChannelError::NoReceivers }));
+        }
+}